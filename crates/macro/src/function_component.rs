@@ -0,0 +1,152 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::parse::{Parse, ParseStream, Result as ParseResult};
+use syn::spanned::Spanned;
+use syn::{Attribute, Block, FnArg, Ident, Item, ReturnType, Type, Visibility};
+
+/// A function which renders properties, turned into a function component.
+pub struct FunctionComponent {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    props_type: Box<Type>,
+    arg: Option<FnArg>,
+    return_type: Box<Type>,
+    block: Box<Block>,
+}
+
+impl Parse for FunctionComponent {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let func = match input.parse::<Item>()? {
+            Item::Fn(func) => func,
+            item => {
+                return Err(syn::Error::new_spanned(
+                    item,
+                    "`function_component` attribute can only be applied to functions",
+                ))
+            }
+        };
+        let sig = &func.sig;
+
+        if !sig.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &sig.generics,
+                "function components can't be generic",
+            ));
+        }
+        if let Some(asyncness) = &sig.asyncness {
+            return Err(syn::Error::new_spanned(
+                asyncness,
+                "function components can't be async",
+            ));
+        }
+
+        let return_type = match &sig.output {
+            ReturnType::Default => {
+                return Err(syn::Error::new_spanned(
+                    sig,
+                    "function components must return `yew::Html`",
+                ))
+            }
+            ReturnType::Type(_, ty) => ty.clone(),
+        };
+
+        let mut inputs = sig.inputs.iter();
+        let (arg, props_type) = match inputs.next() {
+            Some(FnArg::Typed(arg)) => match &*arg.ty {
+                Type::Reference(reference) => {
+                    if let Some(mutability) = &reference.mutability {
+                        return Err(syn::Error::new_spanned(
+                            mutability,
+                            "function components can't take their properties mutably",
+                        ));
+                    }
+                    (Some(FnArg::Typed(arg.clone())), reference.elem.clone())
+                }
+                ty => {
+                    return Err(syn::Error::new_spanned(
+                        ty,
+                        "function components must take their properties by reference",
+                    ))
+                }
+            },
+            Some(arg @ FnArg::Receiver(_)) => {
+                return Err(syn::Error::new_spanned(
+                    arg,
+                    "function components can't take `self`",
+                ))
+            }
+            None => (None, Box::new(syn::parse_quote! { () })),
+        };
+        if let Some(arg) = inputs.next() {
+            return Err(syn::Error::new_spanned(
+                arg,
+                "function components can only take their properties",
+            ));
+        }
+
+        Ok(FunctionComponent {
+            attrs: func.attrs,
+            vis: func.vis,
+            name: sig.ident.clone(),
+            props_type,
+            arg,
+            return_type,
+            block: func.block,
+        })
+    }
+}
+
+/// The name of the component, given to the `function_component` attribute.
+pub struct FunctionComponentName {
+    component_name: Ident,
+}
+
+impl Parse for FunctionComponentName {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        if input.is_empty() {
+            return Err(input.error("expected the name of the component"));
+        }
+        let component_name = input.parse()?;
+        Ok(FunctionComponentName { component_name })
+    }
+}
+
+pub fn function_component_impl(
+    name: FunctionComponentName,
+    component: FunctionComponent,
+) -> TokenStream {
+    let FunctionComponentName { component_name } = name;
+    let FunctionComponent {
+        attrs,
+        vis,
+        name: provider_name,
+        props_type,
+        arg,
+        return_type,
+        block,
+    } = component;
+
+    let arg = arg.map_or_else(|| quote! { _: &() }, |arg| quote! { #arg });
+    let component = quote_spanned! {component_name.span()=>
+        ::yew::functional::FunctionComponent<#provider_name>
+    };
+    let run = quote_spanned! {block.span()=>
+        fn run(#arg) -> #return_type #block
+    };
+
+    quote! {
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #vis struct #provider_name;
+
+        impl ::yew::functional::FunctionProvider for #provider_name {
+            type TProps = #props_type;
+
+            #run
+        }
+
+        #(#attrs)*
+        #vis type #component_name = #component;
+    }
+}
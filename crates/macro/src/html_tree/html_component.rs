@@ -159,6 +159,13 @@ impl ToTokens for HtmlComponent {
             quote! { ::yew::html::NodeRef::default() }
         };
 
+        let set_key = if let Some(key) = props.key() {
+            let key = quote_spanned! { key.span()=> (#key).to_string() };
+            quote! { __yew_vchild.key = Some(#key); }
+        } else {
+            quote! {}
+        };
+
         tokens.extend(quote! {{
             // These validation checks show a nice error message to the user.
             // They do not execute at runtime
@@ -167,7 +174,10 @@ impl ToTokens for HtmlComponent {
                 #validate_props
             }
 
-            ::yew::virtual_dom::VChild::<#ty>::new(#init_props, #node_ref)
+            #[allow(unused_mut)]
+            let mut __yew_vchild = ::yew::virtual_dom::VChild::<#ty>::new(#init_props, #node_ref);
+            #set_key
+            __yew_vchild
         }});
     }
 }
@@ -349,11 +359,13 @@ enum Props {
 struct ListProps {
     props: Vec<HtmlProp>,
     node_ref: Option<Expr>,
+    key: Option<Expr>,
 }
 
 struct WithProps {
     props: Ident,
     node_ref: Option<Expr>,
+    key: Option<Expr>,
 }
 
 impl Props {
@@ -365,8 +377,16 @@ impl Props {
         }
     }
 
+    fn key(&self) -> Option<&Expr> {
+        match self {
+            Props::List(list_props) => list_props.key.as_ref(),
+            Props::With(with_props) => with_props.key.as_ref(),
+            Props::None => None,
+        }
+    }
+
     fn collision_message() -> &'static str {
-        "Using special syntax `with props` along with named prop is not allowed. This rule does not apply to special `ref` prop"
+        "Using special syntax `with props` along with named prop is not allowed. This rule does not apply to special `ref` and `key` props"
     }
 }
 
@@ -374,6 +394,7 @@ impl Parse for Props {
     fn parse(input: ParseStream) -> ParseResult<Self> {
         let mut props = Props::None;
         let mut node_ref: Option<Expr> = None;
+        let mut key: Option<Expr> = None;
 
         while let Some((token, _)) = input.cursor().ident() {
             if token == "with" {
@@ -389,6 +410,7 @@ impl Parse for Props {
                 props = Props::With(Box::new(WithProps {
                     props: input.parse::<Ident>()?,
                     node_ref: None,
+                    key: None,
                 }));
 
                 // Handle optional comma
@@ -411,6 +433,16 @@ impl Parse for Props {
                 continue;
             }
 
+            if prop.label.to_string() == "key" {
+                match key {
+                    None => Ok(()),
+                    Some(_) => Err(syn::Error::new_spanned(&prop.label, "too many keys set")),
+                }?;
+
+                key = Some(prop.value);
+                continue;
+            }
+
             if prop.label.to_string() == "type" {
                 return Err(syn::Error::new_spanned(&prop.label, "expected identifier"));
             }
@@ -424,6 +456,7 @@ impl Parse for Props {
                     *props = Props::List(Box::new(ListProps {
                         props: vec![prop],
                         node_ref: None,
+                        key: None,
                     }));
                 }
                 Props::With(_) => {
@@ -436,10 +469,22 @@ impl Parse for Props {
         }
 
         match props {
-            Props::None => {}
-            Props::With(ref mut p) => p.node_ref = node_ref,
+            Props::None => {
+                if node_ref.is_some() || key.is_some() {
+                    props = Props::List(Box::new(ListProps {
+                        props: Vec::new(),
+                        node_ref,
+                        key,
+                    }));
+                }
+            }
+            Props::With(ref mut p) => {
+                p.node_ref = node_ref;
+                p.key = key;
+            }
             Props::List(ref mut p) => {
                 p.node_ref = node_ref;
+                p.key = key;
 
                 // alphabetize
                 p.props.sort_by(|a, b| {
@@ -90,14 +90,20 @@ impl ToTokens for HtmlTag {
 
         let TagAttributes {
             classes,
+            styles,
             attributes,
+            properties,
             booleans,
             kind,
             value,
+            values,
+            controlled,
             checked,
             node_ref,
+            key,
             href,
             listeners,
+            custom_listeners,
         } = &attributes;
 
         let vtag = Ident::new("__yew_vtag", tag_name.span());
@@ -105,6 +111,10 @@ impl ToTokens for HtmlTag {
             let label_str = label.to_string();
             quote_spanned! {value.span() => (#label_str.to_owned(), (#value).to_string()) }
         });
+        let add_properties = properties.iter().map(|TagAttribute { label, value }| {
+            let label_str = label.to_string();
+            quote_spanned! {value.span()=> #vtag.add_property(#label_str, #value); }
+        });
         let set_booleans = booleans.iter().map(|TagAttribute { label, value }| {
             let label_str = label.to_string();
             quote_spanned! {value.span() =>
@@ -119,6 +129,12 @@ impl ToTokens for HtmlTag {
         let set_value = value.iter().map(|value| {
             quote_spanned! {value.span()=> #vtag.set_value(&(#value)); }
         });
+        let set_values = values.iter().map(|values| {
+            quote_spanned! {values.span()=> #vtag.set_values(#values); }
+        });
+        let set_controlled = controlled.iter().map(|controlled| {
+            quote_spanned! {controlled.span()=> #vtag.set_controlled(#controlled); }
+        });
         let add_href = href.iter().map(|href| {
             quote_spanned! {href.span()=>
                 let __yew_href: ::yew::html::Href = (#href).into();
@@ -136,33 +152,81 @@ impl ToTokens for HtmlTag {
                 #vtag.set_classes(#classes);
             },
         });
+        let set_styles = styles.iter().map(|styles| {
+            quote_spanned! {styles.span()=> #vtag.set_styles(#styles); }
+        });
         let set_node_ref = node_ref.iter().map(|node_ref| {
             quote! {
                 #vtag.node_ref = #node_ref;
             }
         });
+        let set_key = key.iter().map(|key| {
+            quote_spanned! {key.span()=>
+                #vtag.key = Some((#key).to_string());
+            }
+        });
         let listeners = listeners.iter().map(|listener| {
             let name = &listener.label.name;
             let callback = &listener.value;
+            let callback = quote_spanned! {name.span()=>
+                <::yew::virtual_dom::VTag as ::yew::virtual_dom::Transformer<_, _>>::transform(
+                    #callback
+                )
+            };
 
+            let options: Vec<String> = listener
+                .label
+                .extended
+                .iter()
+                .map(|(_, option)| option.to_string())
+                .collect();
+            if options.is_empty() {
+                return quote_spanned! {name.span()=> {
+                    ::yew::html::#name::Wrapper::new(#callback)
+                }};
+            }
+            let passive = options.iter().any(|option| option == "passive");
+            let capture = options.iter().any(|option| option == "capture");
+            let once = options.iter().any(|option| option == "once");
             quote_spanned! {name.span()=> {
-                ::yew::html::#name::Wrapper::new(
+                ::yew::html::#name::Wrapper::with_options(
+                    #callback,
+                    ::yew::virtual_dom::ListenerOptions {
+                        passive: #passive,
+                        capture: #capture,
+                        once: #once,
+                    },
+                )
+            }}
+        });
+        let custom_listeners = custom_listeners.iter().map(|listener| {
+            let kind = listener.label.to_string();
+            let callback = &listener.value;
+            quote_spanned! {callback.span()=>
+                ::yew::html::CustomListener::new(
+                    #kind,
                     <::yew::virtual_dom::VTag as ::yew::virtual_dom::Transformer<_, _>>::transform(
                         #callback
-                    )
+                    ),
                 )
-            }}
+            }
         });
+        let listeners: Vec<_> = listeners.chain(custom_listeners).collect();
 
         tokens.extend(quote! {{
             let mut #vtag = ::yew::virtual_dom::VTag::new(#name);
             #(#set_kind)*
             #(#set_value)*
+            #(#set_values)*
+            #(#set_controlled)*
             #(#add_href)*
             #(#set_checked)*
             #(#set_booleans)*
             #(#set_classes)*
+            #(#set_styles)*
             #(#set_node_ref)*
+            #(#set_key)*
+            #(#add_properties)*
             #vtag.add_attributes(vec![#(#attr_pairs),*]);
             #vtag.add_listeners(vec![#(::std::rc::Rc::new(#listeners)),*]);
             #vtag.add_children(vec![#(#children),*]);
@@ -198,9 +262,10 @@ impl Parse for HtmlTagOpen {
         let TagSuffix { stream, div, gt } = input.parse()?;
         let mut attributes: TagAttributes = parse(stream)?;
 
-        // Don't treat value as special for non input / textarea fields
-        match tag_name.to_string().as_str() {
-            "input" | "textarea" => {}
+        // Don't treat value as special for non input / textarea / select fields
+        let tag = tag_name.to_string();
+        match tag.as_str() {
+            "input" | "textarea" | "select" => {}
             _ => {
                 if let Some(value) = attributes.value.take() {
                     attributes.attributes.push(TagAttribute {
@@ -210,6 +275,24 @@ impl Parse for HtmlTagOpen {
                 }
             }
         }
+        // Only a select can have multiple values
+        if tag != "select" {
+            if let Some(value) = attributes.values.take() {
+                attributes.attributes.push(TagAttribute {
+                    label: TagName::new(Ident::new("values", Span::call_site())),
+                    value,
+                });
+            }
+        }
+        // Only the value of an input or textarea can be controlled
+        if tag != "input" && tag != "textarea" {
+            if let Some(value) = attributes.controlled.take() {
+                attributes.attributes.push(TagAttribute {
+                    label: TagName::new(Ident::new("controlled", Span::call_site())),
+                    value,
+                });
+            }
+        }
 
         Ok(HtmlTagOpen {
             lt,
@@ -1,20 +1,27 @@
 use crate::html_tree::HtmlProp as TagAttribute;
 use crate::PeekValue;
 use lazy_static::lazy_static;
+use proc_macro2::{Ident, Spacing};
 use std::collections::HashSet;
 use std::iter::FromIterator;
 use syn::parse::{Parse, ParseStream, Result as ParseResult};
-use syn::{Expr, ExprTuple};
+use syn::{Expr, ExprTuple, Token};
 
 pub struct TagAttributes {
     pub attributes: Vec<TagAttribute>,
+    pub properties: Vec<TagAttribute>,
     pub listeners: Vec<TagAttribute>,
+    pub custom_listeners: Vec<TagAttribute>,
     pub classes: Option<ClassesForm>,
+    pub styles: Option<Expr>,
     pub booleans: Vec<TagAttribute>,
     pub value: Option<Expr>,
+    pub values: Option<Expr>,
+    pub controlled: Option<Expr>,
     pub kind: Option<Expr>,
     pub checked: Option<Expr>,
     pub node_ref: Option<Expr>,
+    pub key: Option<Expr>,
     pub href: Option<Expr>,
 }
 
@@ -102,13 +109,21 @@ lazy_static! {
     };
 }
 
+/// Options which can be added to the name of a listener, e.g. `onscroll-passive`.
+const LISTENER_OPTIONS: &[&str] = &["passive", "capture", "once"];
+
 impl TagAttributes {
     fn drain_listeners(attrs: &mut Vec<TagAttribute>) -> Vec<TagAttribute> {
         let mut i = 0;
         let mut drained = Vec::new();
         while i < attrs.len() {
-            let name_str = attrs[i].label.to_string();
-            if LISTENER_SET.contains(&name_str.as_str()) {
+            let label = &attrs[i].label;
+            let is_listener = LISTENER_SET.contains(label.name.to_string().as_str())
+                && label
+                    .extended
+                    .iter()
+                    .all(|(_, option)| LISTENER_OPTIONS.contains(&option.to_string().as_str()));
+            if is_listener {
                 drained.push(attrs.remove(i));
             } else {
                 i += 1;
@@ -143,6 +158,27 @@ impl TagAttributes {
         None
     }
 
+    fn check_duplicates(attrs: &mut [TagAttribute], kind: &str) -> ParseResult<()> {
+        attrs.sort_by(|a, b| {
+            a.label
+                .to_string()
+                .partial_cmp(&b.label.to_string())
+                .unwrap()
+        });
+        let mut i = 0;
+        while i + 1 < attrs.len() {
+            if attrs[i].label.to_string() == attrs[i + 1].label.to_string() {
+                let label = &attrs[i + 1].label;
+                return Err(syn::Error::new_spanned(
+                    label,
+                    format!("only one `{}` {} allowed", label, kind),
+                ));
+            }
+            i += 1;
+        }
+        Ok(())
+    }
+
     fn map_classes(class_expr: Expr) -> ClassesForm {
         match class_expr {
             Expr::Tuple(ExprTuple { elems, .. }) => ClassesForm::Tuple(elems.into_iter().collect()),
@@ -154,8 +190,37 @@ impl TagAttributes {
 impl Parse for TagAttributes {
     fn parse(input: ParseStream) -> ParseResult<Self> {
         let mut attributes: Vec<TagAttribute> = Vec::new();
-        while TagAttribute::peek(input.cursor()).is_some() {
-            attributes.push(input.parse::<TagAttribute>()?);
+        let mut properties: Vec<TagAttribute> = Vec::new();
+        let mut custom_listeners: Vec<TagAttribute> = Vec::new();
+        loop {
+            // DOM properties are prefixed with a tilde, e.g. `~indeterminate=true`
+            let is_property = input
+                .cursor()
+                .punct()
+                .filter(|(punct, _)| punct.as_char() == '~')
+                .and_then(|(_, cursor)| TagAttribute::peek(cursor))
+                .is_some();
+            // Listeners for events by name are prefixed with `on:`, e.g. `on:paste=...`
+            let is_custom_listener = input
+                .cursor()
+                .ident()
+                .filter(|(ident, _)| ident == "on")
+                .and_then(|(_, cursor)| cursor.punct())
+                .filter(|(punct, _)| punct.as_char() == ':' && punct.spacing() == Spacing::Alone)
+                .and_then(|(_, cursor)| TagAttribute::peek(cursor))
+                .is_some();
+            if is_property {
+                input.parse::<Token![~]>()?;
+                properties.push(input.parse::<TagAttribute>()?);
+            } else if is_custom_listener {
+                input.parse::<Ident>()?;
+                input.parse::<Token![:]>()?;
+                custom_listeners.push(input.parse::<TagAttribute>()?);
+            } else if TagAttribute::peek(input.cursor()).is_some() {
+                attributes.push(input.parse::<TagAttribute>()?);
+            } else {
+                break;
+            }
         }
 
         let mut listeners = Vec::new();
@@ -164,42 +229,37 @@ impl Parse for TagAttributes {
         }
 
         // Multiple listener attributes are allowed, but no others
-        attributes.sort_by(|a, b| {
-            a.label
-                .to_string()
-                .partial_cmp(&b.label.to_string())
-                .unwrap()
-        });
-        let mut i = 0;
-        while i + 1 < attributes.len() {
-            if attributes[i].label.to_string() == attributes[i + 1].label.to_string() {
-                let label = &attributes[i + 1].label;
-                return Err(syn::Error::new_spanned(
-                    label,
-                    format!("only one `{}` attribute allowed", label),
-                ));
-            }
-            i += 1;
-        }
+        TagAttributes::check_duplicates(&mut attributes, "attribute")?;
+        TagAttributes::check_duplicates(&mut properties, "property")?;
         let booleans = TagAttributes::drain_boolean(&mut attributes);
 
         let classes =
             TagAttributes::remove_attr(&mut attributes, "class").map(TagAttributes::map_classes);
+        let styles = TagAttributes::remove_attr(&mut attributes, "style");
         let value = TagAttributes::remove_attr(&mut attributes, "value");
+        let values = TagAttributes::remove_attr(&mut attributes, "values");
+        let controlled = TagAttributes::remove_attr(&mut attributes, "controlled");
         let kind = TagAttributes::remove_attr(&mut attributes, "type");
         let checked = TagAttributes::remove_attr(&mut attributes, "checked");
         let node_ref = TagAttributes::remove_attr(&mut attributes, "ref");
+        let key = TagAttributes::remove_attr(&mut attributes, "key");
         let href = TagAttributes::remove_attr(&mut attributes, "href");
 
         Ok(TagAttributes {
             attributes,
+            properties,
             classes,
+            styles,
             listeners,
+            custom_listeners,
             checked,
             booleans,
             value,
+            values,
+            controlled,
             kind,
             node_ref,
+            key,
             href,
         })
     }
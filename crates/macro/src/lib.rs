@@ -59,9 +59,11 @@
 extern crate proc_macro;
 
 mod derive_props;
+mod function_component;
 mod html_tree;
 
 use derive_props::DerivePropsInput;
+use function_component::{function_component_impl, FunctionComponent, FunctionComponentName};
 use html_tree::{HtmlRoot, HtmlRootNested};
 use proc_macro::TokenStream;
 use proc_macro_hack::proc_macro_hack;
@@ -93,6 +95,13 @@ pub fn derive_props(input: TokenStream) -> TokenStream {
     TokenStream::from(input.into_token_stream())
 }
 
+#[proc_macro_attribute]
+pub fn function_component(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as FunctionComponent);
+    let attr = parse_macro_input!(attr as FunctionComponentName);
+    TokenStream::from(function_component_impl(attr, item))
+}
+
 #[proc_macro_hack]
 pub fn html_nested(input: TokenStream) -> TokenStream {
     let root = parse_macro_input!(input as HtmlRootNested);
@@ -0,0 +1,41 @@
+#![recursion_limit = "256"]
+use yew::functional::{function_component, use_effect, use_reducer, use_ref, use_state};
+use yew::prelude::*;
+
+#[derive(Clone, Properties, PartialEq)]
+pub struct CounterProps {
+    pub step: u32,
+}
+
+#[function_component(Counter)]
+fn counter(props: &CounterProps) -> Html {
+    let (count, dispatch) = use_reducer(|count: std::rc::Rc<u32>, step: u32| *count + step, 0);
+    let step = props.step;
+    let onclick = Callback::from(move |_| dispatch(step));
+    html! {
+        <button onclick=onclick>{ count }</button>
+    }
+}
+
+/// A greeting.
+#[function_component(Greeting)]
+pub fn greeting() -> Html {
+    let (name, _set_name) = use_state(|| String::from("world"));
+    let renders = use_ref(|| 0);
+    use_effect(move || {
+        *renders.borrow_mut() += 1;
+        || ()
+    });
+    html! { <p>{ format!("Hello {}", name) }</p> }
+}
+
+fn compile_pass() {
+    html! {
+        <>
+            <Counter step=2 />
+            <Greeting />
+        </>
+    };
+}
+
+fn main() {}
@@ -232,6 +232,16 @@ fn compile_pass() {
         </>
     };
 
+    let props = <Child as Component>::Properties::default();
+    html! {
+        <>
+            <Child int=1 key="first" />
+            <Child key=2 int=1 />
+            <Child with props key="third" />
+            <AltChild key="fourth" />
+        </>
+    };
+
     let props = <Container as Component>::Properties::default();
     html! {
         <>
@@ -7,11 +7,17 @@ fn compile_pass() {
     html! {
         <div>
             <div data-key="abc"></div>
+            <div key="abc"></div>
+            <div key=1 />
             <div ref=parent_ref class="parent">
                 <span class="child", value="anything",></span>
                 <label for="first-name">{"First Name"}</label>
                 <input type="text" id="first-name" value="placeholder" />
                 <input type="checkbox" checked=true />
+                <input type="checkbox" ~indeterminate=true />
+                <div style="color: red; margin: 0" />
+                <div style=("color", "red") />
+                <div style=vec![("color", "red"), ("margin", "0")] />
                 <textarea value="write a story" />
                 <select name="status">
                     <option selected=true disabled=false value="">{"Selected"}</option>
@@ -43,6 +49,25 @@ fn compile_pass() {
             </custom-tag-a>
         </div>
     };
+
+    html! {
+        <div>
+            <div onmousewheel-passive=Callback::from(|_| ()) />
+            <div onfocus-capture-once=Callback::from(|_| ()) />
+            <div on:paste=Callback::from(|_: yew::html::RawEvent| ()) />
+            <div on:item-selected=Callback::from(|_: yew::html::RawEvent| ()) />
+            <input controlled=true value="42" />
+            <textarea controlled=true value="story" />
+            <select value="b">
+                <option value="a">{"A"}</option>
+                <option value="b">{"B"}</option>
+            </select>
+            <select multiple=true values=vec!["a", "b"]>
+                <option value="a">{"A"}</option>
+                <option value="b">{"B"}</option>
+            </select>
+        </div>
+    };
 }
 
 fn main() {}
@@ -3,6 +3,8 @@
 fn tests() {
     let t = trybuild::TestCases::new();
 
+    t.pass("tests/macro/function-component-pass.rs");
+
     t.pass("tests/macro/html-block-pass.rs");
     t.compile_fail("tests/macro/html-block-fail.rs");
 
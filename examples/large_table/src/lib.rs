@@ -53,7 +53,7 @@ impl Model {
 
     fn view_row(&self, row: u32) -> Html {
         html! {
-            <tr>
+            <tr key=row>
                 {for (0..99).map(|column| {
                     self.view_square(row, column)
                 })}
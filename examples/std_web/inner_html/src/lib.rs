@@ -1,10 +1,5 @@
 #![recursion_limit = "512"]
-#[macro_use]
-extern crate stdweb;
 
-use stdweb::unstable::TryFrom;
-use stdweb::web::Node;
-use yew::virtual_dom::VNode;
 use yew::{Component, ComponentLink, Html, ShouldRender};
 
 const SVG: &str = r#"
@@ -34,16 +29,6 @@ impl Component for Model {
     }
 
     fn view(&self) -> Html {
-        let js_svg = js! {
-            var div = document.createElement("div");
-            div.innerHTML = @{SVG.to_string()};
-            console.log(div);
-            return div;
-        };
-        eprintln!("js_svg: {:?}", js_svg);
-        let node = Node::try_from(js_svg).expect("convert js_svg");
-        let vnode = VNode::VRef(node);
-        eprintln!("svg: {:?}", vnode);
-        vnode
+        Html::from_html_unchecked(SVG)
     }
 }
@@ -1,7 +1,5 @@
 #![recursion_limit = "512"]
 
-use web_sys::{console, Node};
-use yew::virtual_dom::VNode;
 use yew::{Component, ComponentLink, Html, ShouldRender};
 
 const SVG: &str = r#"
@@ -31,21 +29,6 @@ impl Component for Model {
     }
 
     fn view(&self) -> Html {
-        let js_svg = {
-            let div = web_sys::window()
-                .unwrap()
-                .document()
-                .unwrap()
-                .create_element("div")
-                .unwrap();
-            div.set_inner_html(SVG);
-            console::log_1(&div);
-            div
-        };
-        eprintln!("js_svg: {:?}", js_svg);
-        let node = Node::from(js_svg);
-        let vnode = VNode::VRef(node);
-        eprintln!("svg: {:?}", vnode);
-        vnode
+        Html::from_html_unchecked(SVG)
     }
 }
@@ -3,11 +3,13 @@
 
 use crate::html::{Component, ComponentLink, NodeRef, Scope};
 use crate::utils::document;
+use crate::virtual_dom::{Handle, VList, VNode};
 use cfg_if::cfg_if;
 cfg_if! {
     if #[cfg(feature = "std_web")] {
         use stdweb::web::{Element, INode, IParentNode};
     } else if #[cfg(feature = "web_sys")] {
+        use crate::html::delegation;
         use web_sys::Element;
     }
 }
@@ -17,6 +19,9 @@ cfg_if! {
 pub struct App<COMP: Component> {
     /// `Scope` holder
     scope: Scope<COMP>,
+    /// Handle events of the elements inside of the app at its root
+    #[cfg(feature = "web_sys")]
+    delegate_events: bool,
 }
 
 impl<COMP> Default for App<COMP>
@@ -39,13 +44,24 @@ where
     /// will render the model to a virtual DOM tree. If you would like to pass props,
     /// use the `mount_with_props` method.
     pub fn mount(self, element: Element) -> ComponentLink<COMP> {
-        clear_element(&element);
-        self.scope.mount_in_place(
-            element,
-            None,
-            NodeRef::default(),
-            COMP::Properties::default(),
-        )
+        self.mount_with_props(element, COMP::Properties::default())
+    }
+
+    /// Alternative to `mount` which reuses the markup produced by `render_to_string` inside of
+    /// `element` instead of clearing it. The view is diffed against the existing nodes, so
+    /// only mismatching parts of the markup are patched and a warning is logged for each.
+    /// If you would like to pass props, use the `hydrate_with_props` method.
+    pub fn hydrate(self, element: Element) -> ComponentLink<COMP> {
+        self.hydrate_with_props(element, COMP::Properties::default())
+    }
+
+    /// Renders the component and all of its children to an HTML string without a DOM, which
+    /// makes it usable outside of the browser, e.g. to serve the first paint from a server.
+    /// The markup of nested components is enclosed in `<!--[-->` and `<!--]-->` comments,
+    /// which `hydrate` uses to match the nodes with the components and removes.
+    /// If you would like to pass props, use the `render_to_string_with_props` method.
+    pub fn render_to_string(self) -> String {
+        self.render_to_string_with_props(COMP::Properties::default())
     }
 
     /// Alias to `mount("body", ...)`.
@@ -63,23 +79,7 @@ where
     /// need to manipulate the body element. For example, adding/removing app-wide
     /// CSS classes of the body element.
     pub fn mount_as_body(self) -> ComponentLink<COMP> {
-        let html_element = document()
-            .query_selector("html")
-            .expect("can't get html node for rendering")
-            .expect("can't unwrap html node");
-        let body_element = document()
-            .query_selector("body")
-            .expect("can't get body node for rendering")
-            .expect("can't unwrap body node");
-        html_element
-            .remove_child(&body_element)
-            .expect("can't remove body child");
-        self.scope.mount_in_place(
-            html_element,
-            None,
-            NodeRef::default(),
-            COMP::Properties::default(),
-        )
+        self.mount_as_body_with_props(COMP::Properties::default())
     }
 }
 
@@ -90,7 +90,27 @@ where
     /// Creates a new `App` with a component in a context.
     pub fn new() -> Self {
         let scope = Scope::new();
-        App { scope }
+        App {
+            scope,
+            #[cfg(feature = "web_sys")]
+            delegate_events: false,
+        }
+    }
+
+    /// Makes the element the app is mounted to handle the events of all the elements inside
+    /// of it: it registers a single listener for each kind of event and dispatches events to
+    /// the right callback by walking up from the target of the event. This saves the memory
+    /// and time needed to attach listeners to every element, e.g. in large lists.
+    ///
+    /// Listeners of elements outside of the app, e.g. rendered into a portal or the shadow
+    /// root of a custom element, and listeners with options are still attached directly.
+    ///
+    /// Delegation is only available with the `web_sys` backend. With `std_web` listeners are
+    /// always attached to their elements.
+    #[cfg(feature = "web_sys")]
+    pub fn delegate_events(mut self) -> Self {
+        self.delegate_events = true;
+        self
     }
 
     /// The main entrypoint of a yew program which also allows passing properties. It works
@@ -103,8 +123,27 @@ where
         props: COMP::Properties,
     ) -> ComponentLink<COMP> {
         clear_element(&element);
-        self.scope
-            .mount_in_place(element, None, NodeRef::default(), props)
+        self.mount_root(element, None, props)
+    }
+
+    /// Alternative to `hydrate` which allows passing properties. The properties have to match
+    /// the ones the markup was rendered with to avoid patching it.
+    pub fn hydrate_with_props(
+        self,
+        element: Element,
+        props: COMP::Properties,
+    ) -> ComponentLink<COMP> {
+        let ancestor = VList::adopt_children(&element).into_view_ancestor();
+        self.mount_root(element, Some(ancestor), props)
+    }
+
+    /// Alternative to `render_to_string` which allows passing properties. Components are
+    /// created and rendered synchronously and are never mounted, so their `mounted` method is
+    /// not called and messages sent to them are ignored.
+    pub fn render_to_string_with_props(self, props: COMP::Properties) -> String {
+        let mut html = String::new();
+        self.scope.render_html(props, &mut html);
+        html
     }
 
     /// Alias to `mount_with_props("body", ...)`.
@@ -133,8 +172,27 @@ where
         html_element
             .remove_child(&body_element)
             .expect("can't remove body child");
-        self.scope
-            .mount_in_place(html_element, None, NodeRef::default(), props)
+        self.mount_root(html_element, None, props)
+    }
+
+    fn mount_root(
+        self,
+        element: Element,
+        ancestor: Option<VNode>,
+        props: COMP::Properties,
+    ) -> ComponentLink<COMP> {
+        #[cfg(feature = "web_sys")]
+        {
+            if self.delegate_events {
+                delegation::add_root(element.clone());
+            }
+        }
+        self.scope.mount_in_place(
+            Handle::Browser(element),
+            ancestor,
+            NodeRef::default(),
+            props,
+        )
     }
 }
 
@@ -144,3 +202,336 @@ fn clear_element(element: &Element) {
         element.remove_child(&child).expect("can't remove a child");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::context::ContextProvider;
+    use crate::html::ContextHandle;
+    use crate::macros::Properties;
+    use crate::{html, Html, ShouldRender};
+    use cfg_match::cfg_match;
+    use std::cell::Cell;
+    #[cfg(feature = "std_web")]
+    use stdweb::{_js_impl, js, web::Node};
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+    #[cfg(feature = "web_sys")]
+    use web_sys::Node;
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Clone, Properties)]
+    struct Props {
+        #[prop_or_default]
+        text: String,
+    }
+
+    thread_local! {
+        static CREATED_CHILDREN: Cell<usize> = Cell::new(0);
+    }
+
+    struct Child {
+        props: Props,
+    }
+
+    impl Component for Child {
+        type Message = ();
+        type Properties = Props;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            CREATED_CHILDREN.with(|created| created.set(created.get() + 1));
+            Child { props }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn view(&self) -> Html {
+            html! { <span class="child">{ &self.props.text }</span> }
+        }
+    }
+
+    struct Greeting {
+        props: Props,
+    }
+
+    impl Component for Greeting {
+        type Message = ();
+        type Properties = Props;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            Greeting { props }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn view(&self) -> Html {
+            html! { <p>{ "Hello, " }{ &self.props.text }</p> }
+        }
+    }
+
+    struct Pair {
+        props: Props,
+    }
+
+    impl Component for Pair {
+        type Message = ();
+        type Properties = Props;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            Pair { props }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn view(&self) -> Html {
+            html! {
+                <>
+                    <Child text=&self.props.text />
+                    <Greeting text=&self.props.text />
+                </>
+            }
+        }
+    }
+
+    struct Parent;
+
+    impl Component for Parent {
+        type Message = ();
+        type Properties = Props;
+
+        fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+            // Messages and futures are ignored because the component is never mounted
+            link.send_message(());
+            link.send_future(async {});
+            Parent
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn view(&self) -> Html {
+            html! {
+                <div id="parent" title="\"quoted\"">
+                    <Child text="<b>&</b>" />
+                    <input type="checkbox" checked=true />
+                    <textarea value="story" />
+                </div>
+            }
+        }
+    }
+
+    /// Renders the text provided by the closest `ContextProvider<String>`.
+    struct Themed {
+        text: Option<String>,
+        _subscription: Option<ContextHandle<String>>,
+    }
+
+    impl Component for Themed {
+        type Message = String;
+        type Properties = ();
+
+        fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+            match link.context::<String>(link.callback(|text| text)) {
+                Some((text, subscription)) => Themed {
+                    text: Some(text),
+                    _subscription: Some(subscription),
+                },
+                None => Themed {
+                    text: None,
+                    _subscription: None,
+                },
+            }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn view(&self) -> Html {
+            html! { <b>{ self.text.as_deref().unwrap_or("none") }</b> }
+        }
+    }
+
+    struct Provider {
+        props: Props,
+    }
+
+    impl Component for Provider {
+        type Message = ();
+        type Properties = Props;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            Provider { props }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn view(&self) -> Html {
+            html! {
+                <div>
+                    <ContextProvider<String> context=self.props.text.clone()>
+                        <Themed />
+                    </ContextProvider<String>>
+                    <Themed />
+                </div>
+            }
+        }
+    }
+
+    #[test]
+    fn renders_to_string() {
+        let html = App::<Parent>::new().render_to_string_with_props(Props {
+            text: String::new(),
+        });
+        assert_eq!(
+            html,
+            "<div id=\"parent\" title=\"&quot;quoted&quot;\">\
+             <!--[--><span class=\"child\">&lt;b&gt;&amp;&lt;/b&gt;</span><!--]-->\
+             <input type=\"checkbox\" checked=\"\">\
+             <textarea>story</textarea>\
+             </div>"
+        );
+    }
+
+    #[test]
+    fn renders_provided_context_to_string() {
+        let html = App::<Provider>::new().render_to_string_with_props(Props {
+            text: "provided".into(),
+        });
+        assert_eq!(
+            html,
+            "<div>\
+             <!--[--><!--[--><b>provided</b><!--]--><!--]-->\
+             <!--[--><b>none</b><!--]-->\
+             </div>"
+        );
+    }
+
+    #[test]
+    fn hydrates_rendered_markup() {
+        let props = || Props {
+            text: "hydrated".into(),
+        };
+        let element = document().create_element("div").unwrap();
+        let markup = App::<Child>::new().render_to_string_with_props(props());
+        cfg_match! {
+            feature = "std_web" => js! { @(no_return) @{&element}.innerHTML = @{markup}; },
+            feature = "web_sys" => element.set_inner_html(&markup),
+        };
+        let span = element.first_child().unwrap();
+
+        App::<Child>::new().hydrate_with_props(element.clone(), props());
+
+        let first_child = element.first_child().unwrap();
+        cfg_match! {
+            feature = "std_web" => assert!(first_child.is_same_node(&span)),
+            feature = "web_sys" => assert!(first_child.is_same_node(Some(&span))),
+        };
+        assert!(first_child.next_sibling().is_none());
+        assert_eq!(first_child.text_content(), Some("hydrated".into()));
+    }
+
+    fn set_markup<COMP>(element: &Element, props: COMP::Properties)
+    where
+        COMP: Component,
+        COMP::Properties: Clone,
+    {
+        let markup = App::<COMP>::new().render_to_string_with_props(props);
+        cfg_match! {
+            feature = "std_web" => js! { @(no_return) @{element}.innerHTML = @{markup}; },
+            feature = "web_sys" => element.set_inner_html(&markup),
+        };
+    }
+
+    fn count_siblings(first: Option<Node>) -> usize {
+        let mut count = 0;
+        let mut next_child = first;
+        while let Some(child) = next_child {
+            count += 1;
+            next_child = child.next_sibling();
+        }
+        count
+    }
+
+    /// Returns the children of `element` without the markers of components.
+    fn rendered_nodes(element: &Element) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut next_child = element.first_child();
+        while let Some(child) = next_child {
+            next_child = child.next_sibling();
+            if child.node_name() != "#comment" {
+                nodes.push(child);
+            }
+        }
+        nodes
+    }
+
+    fn is_same_node(node: &Node, other: &Node) -> bool {
+        cfg_match! {
+            feature = "std_web" => node.is_same_node(other),
+            feature = "web_sys" => node.is_same_node(Some(other)),
+        }
+    }
+
+    #[test]
+    fn hydrates_merged_texts() {
+        let props = || Props {
+            text: "hydrated".into(),
+        };
+        let element = document().create_element("div").unwrap();
+        set_markup::<Greeting>(&element, props());
+        let paragraph = element.first_child().unwrap();
+        let text = paragraph.first_child().unwrap();
+        assert_eq!(count_siblings(paragraph.first_child()), 1);
+
+        App::<Greeting>::new().hydrate_with_props(element.clone(), props());
+
+        let first_child = element.first_child().unwrap();
+        let first_text = first_child.first_child().unwrap();
+        assert!(is_same_node(&first_child, &paragraph));
+        assert!(is_same_node(&first_text, &text));
+        assert_eq!(count_siblings(first_child.first_child()), 2);
+        assert_eq!(first_text.text_content(), Some("Hello, ".into()));
+        assert_eq!(first_child.text_content(), Some("Hello, hydrated".into()));
+    }
+
+    #[test]
+    fn hydrates_component_with_fragment_root() {
+        let props = || Props {
+            text: "hydrated".into(),
+        };
+        let element = document().create_element("div").unwrap();
+        set_markup::<Pair>(&element, props());
+        let rendered = rendered_nodes(&element);
+        assert_eq!(rendered.len(), 2);
+        let (span, paragraph) = (&rendered[0], &rendered[1]);
+        CREATED_CHILDREN.with(|created| created.set(0));
+
+        App::<Pair>::new().hydrate_with_props(element.clone(), props());
+
+        // Hydration doesn't create components to find out which nodes they rendered
+        assert_eq!(CREATED_CHILDREN.with(Cell::get), 1);
+
+        assert_eq!(count_siblings(element.first_child()), 2);
+        let first_child = element.first_child().unwrap();
+        let second_child = first_child.next_sibling().unwrap();
+        assert!(is_same_node(&first_child, span));
+        assert!(is_same_node(&second_child, paragraph));
+        assert_eq!(count_siblings(second_child.first_child()), 2);
+        assert_eq!(
+            element.text_content(),
+            Some("hydratedHello, hydrated".into())
+        );
+    }
+}
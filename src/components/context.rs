@@ -0,0 +1,108 @@
+//! This module contains implementation of `ContextProvider` component.
+//! It provides a value to all the components rendered inside of it,
+//! which subscribe to it with `ComponentLink::context`. Example:
+//!
+//! ```
+//!# use yew::{Html, Component, ComponentLink, ShouldRender, html};
+//! use yew::components::context::ContextProvider;
+//! use yew::html::ContextHandle;
+//!
+//! #[derive(Clone, PartialEq)]
+//! struct Theme {
+//!     color: String,
+//! }
+//!
+//! struct ThemedButton {
+//!     theme: Option<Theme>,
+//!     _subscription: Option<ContextHandle<Theme>>,
+//! }
+//!
+//! impl Component for ThemedButton {
+//!     type Message = Theme;
+//!     type Properties = ();
+//!
+//!     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+//!         match link.context::<Theme>(link.callback(|theme| theme)) {
+//!             Some((theme, subscription)) => ThemedButton {
+//!                 theme: Some(theme),
+//!                 _subscription: Some(subscription),
+//!             },
+//!             None => ThemedButton { theme: None, _subscription: None },
+//!         }
+//!     }
+//!
+//!     fn update(&mut self, theme: Self::Message) -> ShouldRender {
+//!         self.theme = Some(theme);
+//!         true
+//!     }
+//!
+//!     fn view(&self) -> Html {
+//!         let color = self.theme.as_ref().map_or("black", |theme| theme.color.as_str());
+//!         html! { <button style=format!("color: {}", color)>{ "Click me" }</button> }
+//!     }
+//! }
+//!
+//! fn view() -> Html {
+//!     let theme = Theme { color: "navy".to_string() };
+//!     html! {
+//!         <ContextProvider<Theme> context=theme>
+//!             <ThemedButton />
+//!         </ContextProvider<Theme>>
+//!     }
+//! }
+//! ```
+
+use crate::html::{
+    Children, Component, ComponentLink, ContextState, Html, Renderable, SharedContext, ShouldRender,
+};
+use crate::macros::Properties;
+use std::fmt;
+
+/// `ContextProvider` component.
+pub struct ContextProvider<T: Clone + PartialEq + 'static> {
+    props: Props<T>,
+    context: SharedContext<T>,
+}
+
+impl<T: Clone + PartialEq + 'static> fmt::Debug for ContextProvider<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ContextProvider<_>")
+    }
+}
+
+/// Properties of `ContextProvider` component.
+#[derive(Clone, Properties, Debug)]
+pub struct Props<T: Clone> {
+    /// Value which is provided to the children.
+    pub context: T,
+    /// Components which can subscribe to the context.
+    #[prop_or_default]
+    pub children: Children,
+}
+
+impl<T: Clone + PartialEq + 'static> Component for ContextProvider<T> {
+    type Message = ();
+    type Properties = Props<T>;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let context = ContextState::new(props.context.clone());
+        link.provide_context(context.clone());
+        Self { props, context }
+    }
+
+    fn update(&mut self, _: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props.context != props.context {
+            ContextState::set(&self.context, props.context.clone());
+        }
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        self.props.children.render()
+    }
+}
@@ -0,0 +1,242 @@
+//! This module contains implementation of `ErrorBoundary` component.
+//! It catches panics and failures of the components rendered inside of it and
+//! shows a fallback instead of them. Example:
+//!
+//! ```
+//!# use yew::{Html, Component, ComponentLink, html};
+//! use yew::components::error_boundary::{ErrorBoundary, Fallback};
+//!
+//!# struct Widget;
+//!# impl Component for Widget {
+//!#     type Message = ();type Properties = ();
+//!#     fn create(props: Self::Properties,link: ComponentLink<Self>) -> Self {unimplemented!()}
+//!#     fn update(&mut self,msg: Self::Message) -> bool {unimplemented!()}
+//!#     fn view(&self) -> Html {unimplemented!()}}
+//! fn view() -> Html {
+//!     let fallback = Fallback::new(|error| html! {
+//!         <p class="error">{ error.to_string() }</p>
+//!     });
+//!     html! {
+//!         <ErrorBoundary fallback=fallback>
+//!             <Widget />
+//!         </ErrorBoundary>
+//!     }
+//! }
+//! ```
+//!
+//! # Limitations
+//!
+//! Panics can only be caught if the target unwinds on panic, see
+//! [`Scope::catch_errors`](../../html/struct.Scope.html#method.catch_errors). That's not
+//! the case for `wasm32-unknown-unknown`, which always aborts: there a panic of any component
+//! still takes down the whole application and the boundary never shows its fallback.
+//!
+//! Components which can fail in the browser should call
+//! [`ComponentLink::fail`](../../html/struct.Scope.html#method.fail) with the error instead
+//! of panicking. The boundary handles it just like a caught panic.
+
+use crate::html::{
+    Children, Component, ComponentError, ComponentLink, Html, Renderable, ShouldRender,
+};
+use crate::macros::Properties;
+use std::fmt;
+use std::rc::Rc;
+
+/// `ErrorBoundary` component.
+#[derive(Debug)]
+pub struct ErrorBoundary {
+    props: Props,
+    error: Option<ComponentError>,
+}
+
+/// Internal message of the component.
+#[derive(Debug)]
+pub enum Msg {
+    /// A component inside of the boundary panicked or failed.
+    Failed(ComponentError),
+}
+
+/// Renders the view which replaces the failed children.
+#[derive(Clone)]
+pub struct Fallback(Rc<dyn Fn(&ComponentError) -> Html>);
+
+impl Fallback {
+    /// Creates a fallback from a function of the caught error.
+    pub fn new<F>(view: F) -> Self
+    where
+        F: Fn(&ComponentError) -> Html + 'static,
+    {
+        Fallback(Rc::new(view))
+    }
+}
+
+impl fmt::Debug for Fallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Fallback<_>")
+    }
+}
+
+/// Properties of `ErrorBoundary` component.
+#[derive(Clone, Properties, Debug)]
+pub struct Props {
+    /// Components which are guarded by the boundary.
+    #[prop_or_default]
+    pub children: Children,
+    /// View to render after one of the children panicked or failed.
+    pub fallback: Fallback,
+}
+
+impl Component for ErrorBoundary {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        link.catch_errors(link.callback(Msg::Failed));
+        Self { props, error: None }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Failed(error) => {
+                // Only the first error is shown, the children are removed after it anyway.
+                if self.error.is_some() {
+                    return false;
+                }
+                self.error = Some(error);
+            }
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        match &self.error {
+            Some(error) => (self.props.fallback.0)(error),
+            None => self.props.children.render(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::virtual_dom::{DomBackend, MemoryDom, VDiff};
+    use crate::{html, Properties};
+    use std::cell::RefCell;
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    type Log = Rc<RefCell<Vec<&'static str>>>;
+
+    #[derive(Clone, Properties)]
+    struct LeafProps {
+        log: Log,
+    }
+
+    struct Leaf {
+        props: LeafProps,
+    }
+
+    impl Component for Leaf {
+        type Message = ();
+        type Properties = LeafProps;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            Leaf { props }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn view(&self) -> Html {
+            html! { <span>{ "leaf" }</span> }
+        }
+    }
+
+    impl Drop for Leaf {
+        fn drop(&mut self) {
+            self.props.log.borrow_mut().push("leaf dropped");
+        }
+    }
+
+    #[derive(Clone, Properties)]
+    struct WidgetProps {
+        log: Log,
+        panics: bool,
+    }
+
+    /// Fails right after it's mounted, either with a panic or through its link.
+    struct Widget {
+        props: WidgetProps,
+        link: ComponentLink<Self>,
+    }
+
+    impl Component for Widget {
+        type Message = ();
+        type Properties = WidgetProps;
+
+        fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+            Widget { props, link }
+        }
+
+        fn mounted(&mut self) -> ShouldRender {
+            if self.props.panics {
+                panic!("broken");
+            }
+            self.link.fail("broken");
+            false
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn view(&self) -> Html {
+            html! {
+                <div>
+                    <Leaf log=self.props.log.clone() />
+                </div>
+            }
+        }
+    }
+
+    fn render_failing_widget(panics: bool) {
+        let dom = MemoryDom;
+        let parent = dom.create_element("div", None);
+        let log = Log::default();
+        let fallback = Fallback::new(|error| html! { <p>{ &error.message }</p> });
+        let mut vnode = html! {
+            <ErrorBoundary fallback=fallback>
+                <Widget log=log.clone() panics=panics />
+            </ErrorBoundary>
+        };
+        vnode.apply(&dom, &parent, None, None);
+
+        // The subtree of the widget is torn down and replaced by the fallback
+        assert_eq!(*log.borrow(), vec!["leaf dropped"]);
+        let children = parent.children();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].tag(), Some("p".into()));
+        assert_eq!(parent.text_content(), "broken");
+    }
+
+    #[test]
+    fn renders_fallback_of_failed_component() {
+        render_failing_widget(false);
+    }
+
+    // Panics abort on wasm32, see the limitations of the module
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn renders_fallback_of_panicked_component() {
+        render_failing_widget(true);
+    }
+}
@@ -1,8 +1,20 @@
 //! This module contains useful components.
-//! At this moment it includes typed `Select` only.
+//! At this moment it includes typed `Select`, `ContextProvider`, `ErrorBoundary` and `Suspense`.
 
+#[doc(hidden)]
+pub mod context;
+#[doc(hidden)]
+pub mod error_boundary;
 #[doc(hidden)]
 pub mod select;
+#[doc(hidden)]
+pub mod suspense;
 
+#[doc(inline)]
+pub use self::context::ContextProvider;
+#[doc(inline)]
+pub use self::error_boundary::ErrorBoundary;
 #[doc(inline)]
 pub use self::select::Select;
+#[doc(inline)]
+pub use self::suspense::Suspense;
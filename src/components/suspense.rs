@@ -0,0 +1,202 @@
+//! This module contains implementation of `Suspense` component.
+//! It shows a fallback while any component rendered inside of it
+//! is suspended. Example:
+//!
+//! ```
+//!# use yew::{Html, Component, ComponentLink, html};
+//! use yew::components::Suspense;
+//! use yew::html::Suspension;
+//!
+//! struct Profile {
+//!     loading: Option<Suspension>,
+//! }
+//!
+//! impl Component for Profile {
+//!     type Message = ();
+//!     type Properties = ();
+//!
+//!     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+//!         // Start loading the profile here and keep the suspension until it's loaded.
+//!         Profile { loading: Some(link.suspend()) }
+//!     }
+//!
+//!     fn update(&mut self, _: Self::Message) -> bool {
+//!         // The profile is loaded, the boundary can show it.
+//!         self.loading.take();
+//!         true
+//!     }
+//!
+//!     fn view(&self) -> Html {
+//!         html! { <p>{ "Profile" }</p> }
+//!     }
+//! }
+//!
+//! fn view() -> Html {
+//!     html! {
+//!         <Suspense fallback=html! { <p>{ "Loading..." }</p> }>
+//!             <Profile />
+//!         </Suspense>
+//!     }
+//! }
+//! ```
+//!
+//! The children stay mounted while the fallback is shown: they are kept in a detached element
+//! and their DOM nodes are moved back once they are resumed.
+
+use crate::html::{Children, Component, ComponentLink, Html, Renderable, ShouldRender};
+use crate::macros::Properties;
+use crate::virtual_dom::VSuspense;
+
+/// `Suspense` component.
+#[derive(Debug)]
+pub struct Suspense {
+    props: Props,
+    pending: usize,
+}
+
+/// Internal message of the component.
+#[derive(Debug)]
+pub enum Msg {
+    /// A component inside of the boundary suspended.
+    Suspended,
+    /// A suspension inside of the boundary was resumed.
+    Resumed,
+}
+
+/// Properties of `Suspense` component.
+#[derive(Clone, Properties, Debug)]
+pub struct Props {
+    /// Components which may suspend.
+    #[prop_or_default]
+    pub children: Children,
+    /// View to render while any of the children is suspended.
+    pub fallback: Html,
+}
+
+impl Component for Suspense {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        link.catch_suspensions(link.callback(|suspended| {
+            if suspended {
+                Msg::Suspended
+            } else {
+                Msg::Resumed
+            }
+        }));
+        Self { props, pending: 0 }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Suspended => {
+                self.pending += 1;
+                self.pending == 1
+            }
+            Msg::Resumed => {
+                self.pending = self.pending.saturating_sub(1);
+                self.pending == 0
+            }
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        VSuspense::new(
+            self.props.children.render(),
+            self.props.fallback.clone(),
+            self.pending > 0,
+        )
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html;
+    use crate::html::Suspension;
+    use crate::virtual_dom::{DomBackend, MemoryDom, VDiff};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    type Links = Rc<RefCell<Vec<ComponentLink<Item>>>>;
+
+    #[derive(Clone, Properties)]
+    struct ItemProps {
+        links: Links,
+    }
+
+    /// Suspends when it's created and resumes on the first message.
+    struct Item {
+        loading: Option<Suspension>,
+    }
+
+    impl Component for Item {
+        type Message = ();
+        type Properties = ItemProps;
+
+        fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+            let loading = Some(link.suspend());
+            props.links.borrow_mut().push(link);
+            Item { loading }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            self.loading.take();
+            true
+        }
+
+        fn view(&self) -> Html {
+            let text = if self.loading.is_some() {
+                "loading"
+            } else {
+                "loaded"
+            };
+            html! { <li>{ text }</li> }
+        }
+    }
+
+    #[test]
+    fn renders_fallback_until_resumed() {
+        let dom = MemoryDom;
+        let parent = dom.create_element("ul", None);
+        let links = Links::default();
+        let mut vnode = html! {
+            <Suspense fallback=html! { <li>{ "fallback" }</li> }>
+                <Item links=links.clone() />
+                <Item links=links.clone() />
+            </Suspense>
+        };
+        vnode.apply(&dom, &parent, None, None);
+
+        // The fallback is rendered in place of the children, without a wrapper, after the
+        // empty text node which keeps the place of the content
+        let children = parent.children();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].text_content(), "");
+        assert_eq!(children[1].tag(), Some("li".into()));
+        assert_eq!(parent.text_content(), "fallback");
+
+        links.borrow()[0].send_message(());
+        assert_eq!(parent.text_content(), "fallback");
+
+        links.borrow()[1].send_message(());
+        let children = parent.children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[1].tag(), Some("li".into()));
+        assert_eq!(parent.text_content(), "loadedloaded");
+        // The components were created once and kept while suspended
+        assert_eq!(links.borrow().len(), 2);
+    }
+}
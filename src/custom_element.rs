@@ -0,0 +1,305 @@
+//! This module contains the `CustomElement` trait and the `define` function which expose a
+//! component as a [custom element](https://developer.mozilla.org/en-US/docs/Web/Web_Components/Using_custom_elements),
+//! so it can be embedded into pages which don't use yew.
+
+use crate::app::App;
+use crate::html::{Component, ComponentUpdate, Scope};
+use crate::utils::document;
+use anyhow::{anyhow, Error};
+use cfg_if::cfg_if;
+use cfg_match::cfg_match;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        #[allow(unused_imports)]
+        use stdweb::{_js_impl, js, Mut};
+        use stdweb::web::{Element, IElement, INode};
+    } else if #[cfg(feature = "web_sys")] {
+        use js_sys::Array;
+        use wasm_bindgen::closure::Closure;
+        use wasm_bindgen::prelude::wasm_bindgen;
+        use wasm_bindgen::JsValue;
+        use web_sys::{Element, ShadowRootInit, ShadowRootMode};
+    }
+}
+
+// A module instead of `Function::new`, which would be blocked by a content security policy
+// without `unsafe-eval`
+#[cfg(feature = "web_sys")]
+#[wasm_bindgen(inline_js = "
+export function define(name, observed, connected, disconnected, changed) {
+    customElements.define(name, class extends HTMLElement {
+        static get observedAttributes() { return observed; }
+        connectedCallback() { connected(this); }
+        disconnectedCallback() { disconnected(this); }
+        attributeChangedCallback() { changed(this); }
+    });
+}
+")]
+extern "C" {
+    #[wasm_bindgen(catch, js_name = define)]
+    fn define_element(
+        name: &str,
+        observed: &Array,
+        connected: &Closure<dyn FnMut(Element)>,
+        disconnected: &Closure<dyn FnMut(Element)>,
+        changed: &Closure<dyn FnMut(Element)>,
+    ) -> Result<(), JsValue>;
+}
+
+/// A component which can be used as a custom element.
+pub trait CustomElement: Component {
+    /// Names of the attributes of the element which are passed to the component. The
+    /// properties of the component are created again whenever one of them changes.
+    fn observed_attributes() -> &'static [&'static str];
+
+    /// Creates the properties of the component from the observed attributes which are set
+    /// on the element.
+    fn properties(attributes: &HashMap<String, String>) -> Self::Properties;
+}
+
+/// A component mounted for an element of the page.
+struct Mounted<COMP: Component> {
+    host: Element,
+    container: Element,
+    scope: Scope<COMP>,
+}
+
+/// Components mounted for the elements of a custom element definition.
+type Registry<COMP> = Rc<RefCell<Vec<Mounted<COMP>>>>;
+
+/// Registers the component as a custom element named `name`, which has to contain a dash.
+///
+/// A component is mounted into the shadow root of an element when the element is added to
+/// the document and destroyed when the element is removed from it, so moving an element
+/// creates its component again. Custom elements can't be undefined, so the definition is
+/// kept until the page is closed.
+///
+/// Returns an error if the browser rejects the definition, e.g. because the name is invalid
+/// or already defined.
+pub fn define<COMP: CustomElement>(name: &str) -> Result<(), Error> {
+    let registry: Registry<COMP> = Rc::new(RefCell::new(Vec::new()));
+    let connected = {
+        let registry = registry.clone();
+        move |host: Element| connect(&registry, host)
+    };
+    let disconnected = {
+        let registry = registry.clone();
+        move |host: Element| disconnect(&registry, &host)
+    };
+    let changed = move |host: Element| change(&registry, &host);
+    let observed = COMP::observed_attributes();
+
+    #[cfg(feature = "std_web")]
+    {
+        let observed: Vec<&str> = observed.to_vec();
+        let error = js! {
+            var observed = @{observed};
+            var connected = @{Mut(connected)};
+            var disconnected = @{Mut(disconnected)};
+            var changed = @{Mut(changed)};
+            try {
+                customElements.define(@{name}, class extends HTMLElement {
+                    static get observedAttributes() {
+                        return observed;
+                    }
+                    connectedCallback() {
+                        connected(this);
+                    }
+                    disconnectedCallback() {
+                        disconnected(this);
+                    }
+                    attributeChangedCallback() {
+                        changed(this);
+                    }
+                });
+            } catch (error) {
+                connected.drop();
+                disconnected.drop();
+                changed.drop();
+                return String(error);
+            }
+        };
+        match error.into_string() {
+            Some(error) => Err(anyhow!("can't define custom element {}: {}", name, error)),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "web_sys")]
+    {
+        let observed: Array = observed
+            .iter()
+            .map(|name| JsValue::from_str(name))
+            .collect();
+        let connected = Closure::wrap(Box::new(connected) as Box<dyn FnMut(Element)>);
+        let disconnected = Closure::wrap(Box::new(disconnected) as Box<dyn FnMut(Element)>);
+        let changed = Closure::wrap(Box::new(changed) as Box<dyn FnMut(Element)>);
+        define_element(name, &observed, &connected, &disconnected, &changed)
+            .map_err(|error| anyhow!("can't define custom element {}: {:?}", name, error))?;
+        // The element can be created as long as the page lives
+        connected.forget();
+        disconnected.forget();
+        changed.forget();
+        Ok(())
+    }
+}
+
+/// Mounts a component into the shadow root of `host`.
+fn connect<COMP: CustomElement>(registry: &Registry<COMP>, host: Element) {
+    let container = document()
+        .create_element("div")
+        .expect("can't create container of custom element");
+    // The container shouldn't affect the layout of the component
+    container
+        .set_attribute("style", "display: contents")
+        .expect("can't set style of container");
+    cfg_match! {
+        feature = "std_web" => js! { @(no_return)
+            var host = @{&host};
+            var shadow = host.shadowRoot || host.attachShadow({ mode: "open" });
+            shadow.appendChild(@{&container});
+        },
+        feature = "web_sys" => host
+            .shadow_root()
+            .map_or_else(|| host.attach_shadow(&ShadowRootInit::new(ShadowRootMode::Open)), Ok)
+            .and_then(|shadow| shadow.append_child(&container))
+            .expect("can't attach shadow root of custom element"),
+    };
+
+    // The registry isn't borrowed while the component renders, since it could contain
+    // elements of the same kind
+    let scope = App::<COMP>::new().mount_with_props(container.clone(), properties::<COMP>(&host));
+    registry.borrow_mut().push(Mounted {
+        host,
+        container,
+        scope,
+    });
+}
+
+/// Destroys the component mounted for `host`.
+fn disconnect<COMP: CustomElement>(registry: &Registry<COMP>, host: &Element) {
+    let mounted = {
+        let mut registry = registry.borrow_mut();
+        match registry.iter().position(|mounted| &mounted.host == host) {
+            Some(index) => registry.remove(index),
+            None => return,
+        }
+    };
+    let Mounted {
+        container,
+        mut scope,
+        ..
+    } = mounted;
+    scope.destroy();
+    if let Some(parent) = container.parent_node() {
+        parent
+            .remove_child(&container)
+            .expect("can't remove container of custom element");
+    }
+}
+
+/// Passes the changed attributes of `host` to its component.
+fn change<COMP: CustomElement>(registry: &Registry<COMP>, host: &Element) {
+    // Attributes change before the element is connected as well
+    let scope = registry
+        .borrow()
+        .iter()
+        .find(|mounted| &mounted.host == host)
+        .map(|mounted| mounted.scope.clone());
+    if let Some(scope) = scope {
+        scope.update(ComponentUpdate::Properties(properties::<COMP>(host)));
+    }
+}
+
+fn properties<COMP: CustomElement>(host: &Element) -> COMP::Properties {
+    let attributes = COMP::observed_attributes()
+        .iter()
+        .filter_map(|name| {
+            host.get_attribute(name)
+                .map(|value| (name.to_string(), value))
+        })
+        .collect();
+    COMP::properties(&attributes)
+}
+
+#[cfg(all(test, feature = "web_sys"))]
+mod tests {
+    use super::*;
+    use crate::{html, ComponentLink, Html, Properties, ShouldRender};
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Clone, Properties)]
+    struct Props {
+        name: String,
+    }
+
+    struct Greeting {
+        props: Props,
+    }
+
+    impl Component for Greeting {
+        type Message = ();
+        type Properties = Props;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            Greeting { props }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn change(&mut self, props: Self::Properties) -> ShouldRender {
+            self.props = props;
+            true
+        }
+
+        fn view(&self) -> Html {
+            html! { <p>{ format!("Hello, {}", self.props.name) }</p> }
+        }
+    }
+
+    impl CustomElement for Greeting {
+        fn observed_attributes() -> &'static [&'static str] {
+            &["name"]
+        }
+
+        fn properties(attributes: &HashMap<String, String>) -> Props {
+            Props {
+                name: attributes.get("name").cloned().unwrap_or_default(),
+            }
+        }
+    }
+
+    #[test]
+    fn mounts_component_into_shadow_root() {
+        define::<Greeting>("yew-greeting").unwrap();
+        let host = document().create_element("yew-greeting").unwrap();
+        host.set_attribute("name", "world").unwrap();
+        let body = document().body().unwrap();
+
+        body.append_child(&host).unwrap();
+        let shadow = host.shadow_root().unwrap();
+        assert_eq!(shadow.text_content(), Some("Hello, world".into()));
+
+        host.set_attribute("name", "yew").unwrap();
+        assert_eq!(shadow.text_content(), Some("Hello, yew".into()));
+
+        body.remove_child(&host).unwrap();
+        assert!(shadow.first_child().is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        define::<Greeting>("yew-defined-once").unwrap();
+        assert!(define::<Greeting>("yew-defined-once").is_err());
+        assert!(define::<Greeting>("greeting").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! This module contains an executor which runs futures on the event loop of the browser and
+//! a handle to drop them before they complete.
+
+use cfg_if::cfg_if;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        use std::task::{RawWaker, RawWakerVTable, Waker};
+        #[allow(unused_imports)]
+        use stdweb::{_js_impl, js, Once};
+    } else if #[cfg(feature = "web_sys")] {
+        use wasm_bindgen_futures::spawn_local;
+    }
+}
+
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+/// A spawned future which can be dropped before it completes.
+pub(crate) struct Spawned {
+    future: RefCell<Option<LocalFuture>>,
+    /// Set when the future completed or was cancelled.
+    done: Cell<bool>,
+}
+
+impl Spawned {
+    /// Runs `future` to completion unless it's cancelled.
+    pub(crate) fn spawn<F>(future: F) -> Rc<Spawned>
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        let spawned = Rc::new(Spawned {
+            future: RefCell::new(Some(Box::pin(future))),
+            done: Cell::new(false),
+        });
+        spawn(Polling(spawned.clone()));
+        spawned
+    }
+
+    /// Drops the future.
+    pub(crate) fn cancel(&self) {
+        self.done.set(true);
+        // The future isn't in its slot while it's polled, then it's dropped after the poll
+        if let Ok(mut future) = self.future.try_borrow_mut() {
+            future.take();
+        }
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.done.get()
+    }
+}
+
+/// Polls a spawned future unless it was cancelled.
+struct Polling(Rc<Spawned>);
+
+impl Future for Polling {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let spawned = &self.0;
+        // The future is taken out of its slot, since it could cancel itself while it's polled
+        let future = spawned.future.borrow_mut().take();
+        let mut future = match future {
+            Some(future) if !spawned.done.get() => future,
+            _ => return Poll::Ready(()),
+        };
+        match future.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                spawned.done.set(true);
+                Poll::Ready(())
+            }
+            Poll::Pending if spawned.done.get() => Poll::Ready(()),
+            Poll::Pending => {
+                *spawned.future.borrow_mut() = Some(future);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(feature = "web_sys")]
+fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    spawn_local(future);
+}
+
+#[cfg(feature = "std_web")]
+fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    let task = Rc::new(Task {
+        future: RefCell::new(Some(Box::pin(future))),
+        scheduled: Cell::new(false),
+    });
+    Task::run(task);
+}
+
+/// A future which is polled whenever it's woken, in a microtask of the browser.
+#[cfg(feature = "std_web")]
+struct Task {
+    future: RefCell<Option<LocalFuture>>,
+    scheduled: Cell<bool>,
+}
+
+#[cfg(feature = "std_web")]
+impl Task {
+    fn run(task: Rc<Task>) {
+        task.scheduled.set(false);
+        let waker = Task::waker(task.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut future = task.future.borrow_mut();
+        let is_ready = match future.as_mut() {
+            Some(future) => future.as_mut().poll(&mut cx).is_ready(),
+            None => false,
+        };
+        if is_ready {
+            future.take();
+        }
+    }
+
+    fn wake(task: Rc<Task>) {
+        // A task is polled once for any number of wakes before it runs
+        if task.scheduled.replace(true) {
+            return;
+        }
+        let run = move || Task::run(task);
+        js! { @(no_return)
+            Promise.resolve().then(@{Once(run)});
+        }
+    }
+
+    // The browser runs a single thread, so the waker doesn't have to be thread safe
+    fn waker(task: Rc<Task>) -> Waker {
+        unsafe fn clone(data: *const ()) -> RawWaker {
+            let task = Rc::from_raw(data as *const Task);
+            let cloned = task.clone();
+            std::mem::forget(task);
+            RawWaker::new(Rc::into_raw(cloned) as *const (), &VTABLE)
+        }
+        unsafe fn wake(data: *const ()) {
+            Task::wake(Rc::from_raw(data as *const Task));
+        }
+        unsafe fn wake_by_ref(data: *const ()) {
+            let task = Rc::from_raw(data as *const Task);
+            Task::wake(task.clone());
+            std::mem::forget(task);
+        }
+        unsafe fn drop(data: *const ()) {
+            std::mem::drop(Rc::from_raw(data as *const Task));
+        }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+        let raw = RawWaker::new(Rc::into_raw(task) as *const (), &VTABLE);
+        unsafe { Waker::from_raw(raw) }
+    }
+}
+
+#[cfg(all(test, feature = "web_sys"))]
+mod tests {
+    use super::*;
+    use futures::future::pending;
+    use futures::task::noop_waker;
+
+    struct DropGuard(Rc<Cell<bool>>);
+
+    impl Drop for DropGuard {
+        fn drop(&mut self) {
+            self.0.set(true);
+        }
+    }
+
+    #[test]
+    fn drops_cancelled_future() {
+        let dropped = Rc::new(Cell::new(false));
+        let guard = DropGuard(dropped.clone());
+        let spawned = Rc::new(Spawned {
+            future: RefCell::new(Some(Box::pin(async move {
+                let _guard = guard;
+                pending::<()>().await;
+            }))),
+            done: Cell::new(false),
+        });
+        let mut polling = Polling(spawned.clone());
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(Pin::new(&mut polling).poll(&mut cx).is_pending());
+        assert!(!dropped.get());
+        spawned.cancel();
+        assert!(dropped.get());
+        assert!(spawned.is_done());
+        assert!(Pin::new(&mut polling).poll(&mut cx).is_ready());
+    }
+}
@@ -0,0 +1,171 @@
+//! Hooks which keep the state of a function component.
+
+use super::{push_effect, use_hook, Hook};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+struct UseState<T> {
+    current: Rc<T>,
+}
+
+impl<T: 'static> Hook for UseState<T> {}
+
+/// Keeps a value which is created with `initial_state` the first time the component
+/// renders. Returns the current value and a function which replaces it and renders the
+/// component again.
+pub fn use_state<T, F>(initial_state: F) -> (Rc<T>, Rc<dyn Fn(T)>)
+where
+    T: 'static,
+    F: FnOnce() -> T,
+{
+    use_hook(
+        || UseState {
+            current: Rc::new(initial_state()),
+        },
+        |hook, updater| {
+            let setter = {
+                let hook = hook.clone();
+                move |value: T| {
+                    let hook = hook.clone();
+                    updater.update(move || {
+                        hook.borrow_mut().current = Rc::new(value);
+                        true
+                    });
+                }
+            };
+            let current = hook.borrow().current.clone();
+            (current, Rc::new(setter) as Rc<dyn Fn(T)>)
+        },
+    )
+}
+
+/// Keeps a state which is changed by dispatching actions to `reducer`, which returns the
+/// next state. Returns the current state and a function which dispatches an action and
+/// renders the component again.
+pub fn use_reducer<Action, Reducer, State>(
+    reducer: Reducer,
+    initial_state: State,
+) -> (Rc<State>, Rc<dyn Fn(Action)>)
+where
+    Action: 'static,
+    Reducer: Fn(Rc<State>, Action) -> State + 'static,
+    State: 'static,
+{
+    let reducer = Rc::new(reducer);
+    use_hook(
+        || UseState {
+            current: Rc::new(initial_state),
+        },
+        |hook, updater| {
+            let dispatch = {
+                let hook = hook.clone();
+                move |action: Action| {
+                    let hook = hook.clone();
+                    let reducer = reducer.clone();
+                    updater.update(move || {
+                        let current = hook.borrow().current.clone();
+                        let next = reducer(current, action);
+                        hook.borrow_mut().current = Rc::new(next);
+                        true
+                    });
+                }
+            };
+            let current = hook.borrow().current.clone();
+            (current, Rc::new(dispatch) as Rc<dyn Fn(Action)>)
+        },
+    )
+}
+
+struct UseRef<T> {
+    current: Rc<RefCell<T>>,
+}
+
+impl<T: 'static> Hook for UseRef<T> {}
+
+/// Keeps a mutable value which is created with `initial_value` the first time the
+/// component renders. Changing the value doesn't render the component again.
+pub fn use_ref<T, F>(initial_value: F) -> Rc<RefCell<T>>
+where
+    T: 'static,
+    F: FnOnce() -> T,
+{
+    use_hook(
+        || UseRef {
+            current: Rc::new(RefCell::new(initial_value())),
+        },
+        |hook, _| hook.borrow().current.clone(),
+    )
+}
+
+struct UseEffect<Deps> {
+    deps: Option<Rc<Deps>>,
+    destructor: Option<Box<dyn FnOnce()>>,
+}
+
+impl<Deps: 'static> Hook for UseEffect<Deps> {
+    fn tear_down(&mut self) {
+        if let Some(destructor) = self.destructor.take() {
+            destructor();
+        }
+    }
+}
+
+/// Runs `callback` after every render of the component. The function returned by the
+/// callback cleans up after it, before the callback runs again or when the component is
+/// destroyed.
+pub fn use_effect<F, D>(callback: F)
+where
+    F: FnOnce() -> D + 'static,
+    D: FnOnce() + 'static,
+{
+    use_hook(
+        || UseEffect::<()> {
+            deps: None,
+            destructor: None,
+        },
+        |hook, _| {
+            let hook = hook.clone();
+            push_effect(move || run_effect(&hook, callback));
+        },
+    )
+}
+
+/// Alternative to `use_effect` which only runs `callback` again if `deps` changed since
+/// it ran last time.
+pub fn use_effect_with_deps<F, D, Deps>(callback: F, deps: Deps)
+where
+    F: FnOnce(&Deps) -> D + 'static,
+    D: FnOnce() + 'static,
+    Deps: PartialEq + 'static,
+{
+    use_hook(
+        || UseEffect::<Deps> {
+            deps: None,
+            destructor: None,
+        },
+        |hook, _| {
+            if hook.borrow().deps.as_deref() == Some(&deps) {
+                return;
+            }
+            let deps = Rc::new(deps);
+            hook.borrow_mut().deps = Some(deps.clone());
+            let hook = hook.clone();
+            push_effect(move || run_effect(&hook, move || callback(&deps)));
+        },
+    )
+}
+
+fn run_effect<Deps, F, D>(hook: &Rc<RefCell<UseEffect<Deps>>>, callback: F)
+where
+    Deps: 'static,
+    F: FnOnce() -> D,
+    D: FnOnce() + 'static,
+{
+    // The effect could update the state, so the hook isn't borrowed while it runs
+    let destructor = hook.borrow_mut().destructor.take();
+    if let Some(destructor) = destructor {
+        destructor();
+    }
+    let destructor = callback();
+    hook.borrow_mut().destructor = Some(Box::new(destructor));
+}
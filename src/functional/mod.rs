@@ -0,0 +1,362 @@
+//! This module contains function components: components defined by a function which renders
+//! their properties and keeps its state in hooks.
+//!
+//! # Example
+//! ```
+//!# use yew::functional::{function_component, use_state};
+//!# use yew::{html, Callback, Html};
+//! #[function_component(Counter)]
+//! fn counter() -> Html {
+//!     let (count, set_count) = use_state(|| 0);
+//!     let label = format!("Clicked {} times", count);
+//!     let onclick = Callback::from(move |_| set_count(*count + 1));
+//!     html! {
+//!         <button onclick=onclick>{ label }</button>
+//!     }
+//! }
+//!# fn main() {}
+//! ```
+
+mod hooks;
+
+pub use hooks::*;
+/// Turns a function which renders properties into a function component with the given name.
+pub use yew_macro::function_component;
+
+use crate::html::{Component, ComponentLink, Html, Properties, ShouldRender};
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::rc::Rc;
+
+thread_local! {
+    /// The hooks of the function component which is rendering.
+    static CURRENT_HOOKS: RefCell<Option<Rc<RefCell<HookState>>>> = RefCell::new(None);
+}
+
+/// Renders a function component, usually implemented with the `function_component` attribute.
+pub trait FunctionProvider {
+    /// Properties of the component.
+    type TProps: Properties + PartialEq;
+
+    /// Renders the component. The hooks have to be called in the same order every time.
+    fn run(props: &Self::TProps) -> Html;
+}
+
+/// An update of the state of a hook, which returns if the component has to render again.
+type HookUpdate = Box<dyn FnOnce() -> ShouldRender>;
+
+/// State of a hook which lives as long as its component.
+trait Hook: 'static {
+    /// Called when the component is destroyed.
+    fn tear_down(&mut self) {}
+}
+
+/// Hooks of a function component.
+struct HookState {
+    /// Index of the next hook called while the component renders.
+    counter: usize,
+    /// Sends updates of hooks to the component.
+    process: Rc<dyn Fn(HookUpdate)>,
+    hooks: Vec<Rc<dyn Any>>,
+    /// Effects which run after the component rendered.
+    effects: Vec<Box<dyn FnOnce()>>,
+    destroy_listeners: Vec<Box<dyn FnOnce()>>,
+}
+
+/// Sends updates of the state of a hook to its component.
+#[derive(Clone)]
+struct HookUpdater {
+    process: Rc<dyn Fn(HookUpdate)>,
+}
+
+impl HookUpdater {
+    fn update<F>(&self, update: F)
+    where
+        F: FnOnce() -> ShouldRender + 'static,
+    {
+        (self.process)(Box::new(update));
+    }
+}
+
+/// Gives a hook access to its state, which is created the first time the component renders.
+fn use_hook<S, R>(
+    initializer: impl FnOnce() -> S,
+    runner: impl FnOnce(&Rc<RefCell<S>>, HookUpdater) -> R,
+) -> R
+where
+    S: Hook,
+{
+    let (hook, updater) = with_current_hooks(|state| {
+        let index = state.counter;
+        state.counter += 1;
+        if index == state.hooks.len() {
+            let hook = Rc::new(RefCell::new(initializer()));
+            let tear_down = hook.clone();
+            state
+                .destroy_listeners
+                .push(Box::new(move || tear_down.borrow_mut().tear_down()));
+            state.hooks.push(hook);
+        }
+        let hook = state.hooks[index]
+            .clone()
+            .downcast::<RefCell<S>>()
+            .expect("hooks have to be called in the same order every time");
+        let updater = HookUpdater {
+            process: state.process.clone(),
+        };
+        (hook, updater)
+    });
+    // The hooks aren't borrowed while the hook runs, since it could use other hooks
+    runner(&hook, updater)
+}
+
+/// Runs `effect` after the component rendered.
+fn push_effect(effect: impl FnOnce() + 'static) {
+    with_current_hooks(|state| state.effects.push(Box::new(effect)));
+}
+
+/// Makes hooks use the state of a component until it's dropped, even if the component
+/// panics while it renders.
+struct CurrentHooks {
+    outer: Option<Rc<RefCell<HookState>>>,
+}
+
+impl CurrentHooks {
+    fn set(state: Rc<RefCell<HookState>>) -> Self {
+        let outer = CURRENT_HOOKS.with(|current| current.replace(Some(state)));
+        CurrentHooks { outer }
+    }
+}
+
+impl Drop for CurrentHooks {
+    fn drop(&mut self) {
+        let outer = self.outer.take();
+        CURRENT_HOOKS.with(|current| *current.borrow_mut() = outer);
+    }
+}
+
+fn with_current_hooks<R>(f: impl FnOnce(&mut HookState) -> R) -> R {
+    CURRENT_HOOKS.with(|current| {
+        let current = current.borrow();
+        let state = current
+            .as_ref()
+            .expect("hooks can only be used while a function component renders");
+        let mut state = state.borrow_mut();
+        f(&mut state)
+    })
+}
+
+/// A component which renders with a `FunctionProvider`.
+pub struct FunctionComponent<T: FunctionProvider + 'static> {
+    props: T::TProps,
+    hook_state: Rc<RefCell<HookState>>,
+    _provider: PhantomData<T>,
+}
+
+impl<T: FunctionProvider + 'static> fmt::Debug for FunctionComponent<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FunctionComponent<_>")
+    }
+}
+
+impl<T: FunctionProvider + 'static> Component for FunctionComponent<T> {
+    type Message = HookUpdate;
+    type Properties = T::TProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let process = Rc::new(move |update| link.send_message(update));
+        let hook_state = HookState {
+            counter: 0,
+            process,
+            hooks: Vec::new(),
+            effects: Vec::new(),
+            destroy_listeners: Vec::new(),
+        };
+        FunctionComponent {
+            props,
+            hook_state: Rc::new(RefCell::new(hook_state)),
+            _provider: PhantomData,
+        }
+    }
+
+    fn update(&mut self, update: Self::Message) -> ShouldRender {
+        update()
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props == props {
+            false
+        } else {
+            self.props = props;
+            true
+        }
+    }
+
+    fn view(&self) -> Html {
+        self.hook_state.borrow_mut().counter = 0;
+        let _hooks = CurrentHooks::set(self.hook_state.clone());
+        T::run(&self.props)
+    }
+
+    fn rendered(&mut self, _first_render: bool) {
+        let effects = mem::take(&mut self.hook_state.borrow_mut().effects);
+        for effect in effects {
+            effect();
+        }
+    }
+}
+
+impl<T: FunctionProvider + 'static> Drop for FunctionComponent<T> {
+    fn drop(&mut self) {
+        let destroy_listeners = mem::take(&mut self.hook_state.borrow_mut().destroy_listeners);
+        for listener in destroy_listeners {
+            listener();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+    use crate::virtual_dom::{DomBackend, MemoryDom, VDiff};
+    use crate::{html, Properties};
+    use std::panic::{self, AssertUnwindSafe};
+
+    struct Greeting;
+
+    impl FunctionProvider for Greeting {
+        type TProps = ();
+
+        fn run(_: &()) -> Html {
+            let (name, _) = use_state(|| "world");
+            let renders = use_ref(|| 0);
+            *renders.borrow_mut() += 1;
+            html! { <p>{ format!("Hello {} #{}", name, renders.borrow()) }</p> }
+        }
+    }
+
+    #[test]
+    fn renders_with_initial_state() {
+        let html = App::<FunctionComponent<Greeting>>::new().render_to_string();
+        assert_eq!(html, "<p>Hello world #1</p>");
+    }
+
+    #[test]
+    fn keeps_state_of_hooks() {
+        let updates = Rc::new(RefCell::new(Vec::new()));
+        let process = {
+            let updates = updates.clone();
+            Rc::new(move |update| updates.borrow_mut().push(update))
+        };
+        let hook_state = Rc::new(RefCell::new(HookState {
+            counter: 0,
+            process,
+            hooks: Vec::new(),
+            effects: Vec::new(),
+            destroy_listeners: Vec::new(),
+        }));
+        let render = || {
+            hook_state.borrow_mut().counter = 0;
+            let _hooks = CurrentHooks::set(hook_state.clone());
+            let (count, dispatch) = use_reducer(|count: Rc<u32>, step: u32| *count + step, 1);
+            (*count, dispatch)
+        };
+
+        let (count, dispatch) = render();
+        assert_eq!(count, 1);
+        dispatch(2);
+        let should_render = updates.borrow_mut().pop().unwrap()();
+        assert!(should_render);
+        assert_eq!(render().0, 3);
+    }
+
+    struct Broken;
+
+    impl FunctionProvider for Broken {
+        type TProps = ();
+
+        fn run(_: &()) -> Html {
+            use_state(|| ());
+            panic!("broken");
+        }
+    }
+
+    #[test]
+    fn restores_hooks_after_panic() {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            App::<FunctionComponent<Broken>>::new().render_to_string()
+        }));
+        assert!(result.is_err());
+        assert!(CURRENT_HOOKS.with(|current| current.borrow().is_none()));
+    }
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    #[derive(Clone, PartialEq, Properties)]
+    struct EffectProps {
+        generation: u32,
+        log: Log,
+    }
+
+    /// Logs an effect which runs after every render and one which only runs again when the
+    /// generation becomes even.
+    struct Effects;
+
+    impl FunctionProvider for Effects {
+        type TProps = EffectProps;
+
+        fn run(props: &EffectProps) -> Html {
+            let generation = props.generation;
+            let log = props.log.clone();
+            use_effect(move || {
+                log.borrow_mut().push(format!("effect {}", generation));
+                move || log.borrow_mut().push(format!("cleanup {}", generation))
+            });
+            let log = props.log.clone();
+            use_effect_with_deps(
+                move |half: &u32| {
+                    let half = *half;
+                    log.borrow_mut().push(format!("deps effect {}", half));
+                    move || log.borrow_mut().push(format!("deps cleanup {}", half))
+                },
+                generation / 2,
+            );
+            html! { <p>{ generation }</p> }
+        }
+    }
+
+    #[test]
+    fn cleans_up_effects() {
+        let parent = MemoryDom.create_element("div", None);
+        let log = Log::default();
+        let view = |generation| {
+            html! { <FunctionComponent<Effects> generation=generation log=log.clone() /> }
+        };
+        let take_log = || log.borrow_mut().drain(..).collect::<Vec<_>>();
+
+        let mut ancestor = view(0);
+        ancestor.apply(&MemoryDom, &parent, None, None);
+        assert_eq!(take_log(), vec!["effect 0", "deps effect 0"]);
+
+        // The dependencies didn't change, so only the first effect runs again
+        let mut vnode = view(1);
+        vnode.apply(&MemoryDom, &parent, None, Some(ancestor));
+        assert_eq!(parent.text_content(), "1");
+        assert_eq!(take_log(), vec!["cleanup 0", "effect 1"]);
+
+        let ancestor = vnode;
+        let mut vnode = view(2);
+        vnode.apply(&MemoryDom, &parent, None, Some(ancestor));
+        assert_eq!(
+            take_log(),
+            vec!["cleanup 1", "effect 2", "deps cleanup 0", "deps effect 1"]
+        );
+
+        vnode.detach(&MemoryDom, &parent);
+        assert_eq!(take_log(), vec!["cleanup 2", "deps cleanup 1"]);
+    }
+}
@@ -0,0 +1,112 @@
+//! This module contains the state of contexts, which are values provided to all the
+//! descendants of a component.
+
+use crate::callback::Callback;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+/// The value of a context and the callbacks of the components which subscribed to it.
+pub(crate) struct ContextState<T> {
+    value: T,
+    subscribers: Vec<(u32, Callback<T>)>,
+    next_id: u32,
+}
+
+/// A context which is shared by its provider and its subscribers.
+pub(crate) type SharedContext<T> = Rc<RefCell<ContextState<T>>>;
+
+impl<T: Clone + 'static> ContextState<T> {
+    pub(crate) fn new(value: T) -> SharedContext<T> {
+        Rc::new(RefCell::new(ContextState {
+            value,
+            subscribers: Vec::new(),
+            next_id: 0,
+        }))
+    }
+
+    /// Changes the value of the context and sends it to all subscribers.
+    pub(crate) fn set(context: &SharedContext<T>, value: T) {
+        let subscribers: Vec<Callback<T>> = {
+            let mut state = context.borrow_mut();
+            state.value = value.clone();
+            state
+                .subscribers
+                .iter()
+                .map(|(_, callback)| callback.clone())
+                .collect()
+        };
+        // Subscribers could subscribe or unsubscribe, so the state isn't borrowed
+        for callback in subscribers {
+            callback.emit(value.clone());
+        }
+    }
+
+    /// Returns the current value and calls `callback` whenever it changes until the
+    /// returned handle is dropped.
+    pub(crate) fn subscribe(
+        context: &SharedContext<T>,
+        callback: Callback<T>,
+    ) -> (T, ContextHandle<T>) {
+        let mut state = context.borrow_mut();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.subscribers.push((id, callback));
+        let handle = ContextHandle {
+            context: Rc::downgrade(context),
+            id,
+        };
+        (state.value.clone(), handle)
+    }
+}
+
+/// A subscription of a component to a context, which ends when it's dropped.
+///
+/// See [`Scope::context`](struct.Scope.html#method.context).
+#[must_use = "the subscription ends when the handle is dropped"]
+pub struct ContextHandle<T> {
+    context: Weak<RefCell<ContextState<T>>>,
+    id: u32,
+}
+
+impl<T> fmt::Debug for ContextHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ContextHandle<_>")
+    }
+}
+
+impl<T> Drop for ContextHandle<T> {
+    fn drop(&mut self) {
+        // The provider could be gone already
+        if let Some(context) = self.context.upgrade() {
+            let id = self.id;
+            context
+                .borrow_mut()
+                .subscribers
+                .retain(|(subscriber, _)| *subscriber != id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifies_subscribers_until_dropped() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let callback = {
+            let received = received.clone();
+            Callback::from(move |value| received.borrow_mut().push(value))
+        };
+        let context = ContextState::new("light");
+
+        let (value, handle) = ContextState::subscribe(&context, callback);
+        assert_eq!(value, "light");
+        ContextState::set(&context, "dark");
+        drop(handle);
+        ContextState::set(&context, "light");
+
+        assert_eq!(*received.borrow(), vec!["dark"]);
+    }
+}
@@ -0,0 +1,172 @@
+//! Listeners for events by name, e.g. `CustomEvent`s dispatched by custom elements or events
+//! which don't have a listener of their own.
+
+use crate::callback::Callback;
+use crate::format::{Json, Text};
+use crate::virtual_dom::{vtag, Browser, Listener};
+use anyhow::anyhow;
+use cfg_if::cfg_if;
+use cfg_match::cfg_match;
+use serde::de::DeserializeOwned;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        use super::EventListener;
+        use stdweb::unstable::TryInto;
+        use stdweb::web::Element;
+        #[allow(unused_imports)]
+        use stdweb::{_js_impl, js};
+    } else if #[cfg(feature = "web_sys")] {
+        use gloo::events::{EventListener, EventListenerOptions};
+        use wasm_bindgen::JsCast;
+        use web_sys::{Element, EventTarget};
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        /// An event of any kind, as dispatched by the browser.
+        pub type RawEvent = stdweb::Reference;
+    } else if #[cfg(feature = "web_sys")] {
+        /// An event of any kind, as dispatched by the browser.
+        pub type RawEvent = web_sys::Event;
+    }
+}
+
+/// A type a listener for an event by name can be called with, which is created from the
+/// raw event.
+pub trait FromEvent: Sized + 'static {
+    /// Creates the value from the event.
+    fn from_event(event: RawEvent) -> Self;
+}
+
+impl FromEvent for RawEvent {
+    fn from_event(event: RawEvent) -> Self {
+        event
+    }
+}
+
+#[cfg(feature = "web_sys")]
+impl FromEvent for web_sys::CustomEvent {
+    fn from_event(event: RawEvent) -> Self {
+        event.unchecked_into()
+    }
+}
+
+/// Deserializes the `detail` of a
+/// [CustomEvent](https://developer.mozilla.org/en-US/docs/Web/API/CustomEvent/detail).
+impl<T> FromEvent for Json<Result<T, anyhow::Error>>
+where
+    T: DeserializeOwned + 'static,
+{
+    fn from_event(event: RawEvent) -> Self {
+        Json::from(event_detail(&event))
+    }
+}
+
+fn event_detail(event: &RawEvent) -> Text {
+    let detail: Option<String> = cfg_match! {
+        feature = "std_web" => js!( return JSON.stringify(@{event}.detail); ).try_into().ok(),
+        feature = "web_sys" => event
+            .dyn_ref::<web_sys::CustomEvent>()
+            .and_then(|event| js_sys::JSON::stringify(&event.detail()).ok())
+            .and_then(|detail| detail.as_string()),
+    };
+    detail.ok_or_else(|| anyhow!("event has no detail"))
+}
+
+/// A callback which converts the raw event to the type it's called with.
+trait EventCallback {
+    fn emit(&self, event: RawEvent);
+}
+
+impl<T: FromEvent> EventCallback for Callback<T> {
+    fn emit(&self, event: RawEvent) {
+        Callback::emit(self, T::from_event(event));
+    }
+}
+
+/// A listener for events by their name, e.g. `on:paste=...` or `on:item-selected=...` in
+/// `html!`. The callback gets a [`RawEvent`](type.RawEvent.html) or any other type which
+/// implements [`FromEvent`](trait.FromEvent.html), like `Json<Result<T, Error>>` for the
+/// `detail` of a `CustomEvent`.
+///
+/// Listeners for events by name can't have options.
+#[derive(Clone)]
+pub struct CustomListener {
+    kind: &'static str,
+    /// Shared with the attached listener, so that the callback can be swapped.
+    callback: Rc<RefCell<Rc<dyn EventCallback>>>,
+}
+
+impl CustomListener {
+    /// Creates a listener for events named `kind`.
+    pub fn new<T: FromEvent>(kind: &'static str, callback: Callback<T>) -> Self {
+        CustomListener {
+            kind,
+            callback: Rc::new(RefCell::new(Rc::new(callback))),
+        }
+    }
+}
+
+impl fmt::Debug for CustomListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CustomListener {{ kind: {} }}", self.kind)
+    }
+}
+
+impl Listener for CustomListener {
+    fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    fn take_callback(&self, other: &dyn Listener) -> bool {
+        match other.as_any().downcast_ref::<CustomListener>() {
+            Some(other) if self.kind == other.kind => {
+                let callback = other.callback.borrow().clone();
+                *self.callback.borrow_mut() = callback;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[cfg(feature = "web_sys")]
+    fn delegated_event(&self) -> Option<&'static str> {
+        Some(self.kind)
+    }
+
+    #[cfg(feature = "web_sys")]
+    fn handle(&self, element: &Element, event: &web_sys::Event) {
+        // The callback can be swapped by a render the callback triggers
+        let callback = self.callback.borrow().clone();
+        callback.emit(event.clone());
+        vtag::restore_controlled(&Browser, element);
+    }
+
+    fn attach(&self, element: &Element) -> EventListener {
+        let this = element.clone();
+        let callback = self.callback.clone();
+        let listener = move |event: RawEvent| {
+            cfg_match! {
+                feature = "std_web" => js! { @(no_return) @{&event}.stopPropagation(); },
+                feature = "web_sys" => event.stop_propagation(),
+            };
+            let callback = callback.borrow().clone();
+            callback.emit(event);
+            vtag::restore_controlled(&Browser, &this);
+        };
+        cfg_match! {
+            feature = "std_web" => EventListener::with_kind(element, self.kind, listener),
+            feature = "web_sys" => EventListener::new_with_options(
+                &EventTarget::from(element.clone()),
+                self.kind,
+                EventListenerOptions::enable_prevent_default(),
+                move |event| listener(event.clone()),
+            ),
+        }
+    }
+}
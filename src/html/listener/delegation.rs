@@ -0,0 +1,367 @@
+//! Event delegation: instead of attaching listeners to every element, the root of the app
+//! gets a single listener for each kind of event which dispatches events to the listeners
+//! registered for the target element or its nearest ancestor which has any.
+//!
+//! Delegation is only implemented for the `web_sys` backend.
+
+use crate::virtual_dom::Listener;
+use gloo::events::{EventListener, EventListenerOptions, EventListenerPhase};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, Event, EventTarget};
+
+/// The name of the property which identifies an element in the registry.
+const ID_PROPERTY: &str = "__yew_listener_id";
+
+/// Identifies the listeners of an element by the id of the element and the name of the event.
+type Key = (u32, &'static str);
+
+/// A registered listener, tagged with the id of its registration and the root which
+/// dispatches events to it.
+type Registration = (u32, Rc<dyn Listener>, Element);
+
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry::default());
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Elements which delegate events with the listeners attached to them.
+    roots: Vec<(Element, Vec<EventListener>)>,
+    /// The names of the events delegated so far, which new roots delegate as well.
+    events: Vec<&'static str>,
+    /// Registered listeners by element id and event name.
+    listeners: HashMap<Key, Vec<Registration>>,
+    next_id: u32,
+}
+
+impl Registry {
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+/// Keeps a listener registered for delegation until it's dropped.
+#[derive(Debug)]
+pub(crate) struct Delegated {
+    key: Key,
+    registration: u32,
+}
+
+impl Drop for Delegated {
+    fn drop(&mut self) {
+        REGISTRY.with(|registry| {
+            let mut registry = registry.borrow_mut();
+            if let Some(listeners) = registry.listeners.get_mut(&self.key) {
+                listeners.retain(|(registration, _, _)| *registration != self.registration);
+                if listeners.is_empty() {
+                    registry.listeners.remove(&self.key);
+                }
+            }
+        });
+    }
+}
+
+/// Makes `root` delegate events for all the elements inside of it.
+pub(crate) fn add_root(root: Element) {
+    REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        let delegates = registry
+            .events
+            .iter()
+            .flat_map(|name| delegate(&root, name))
+            .collect();
+        registry.roots.push((root, delegates));
+    });
+}
+
+/// Stops delegating events from `root`. The listeners registered for the elements inside of
+/// it are not called anymore.
+// Apps can't be unmounted yet, so only tests remove roots
+#[allow(dead_code)]
+pub(crate) fn remove_root(root: &Element) {
+    REGISTRY.with(|registry| {
+        registry
+            .borrow_mut()
+            .roots
+            .retain(|(element, _)| element != root);
+    });
+}
+
+/// Registers `listener` for `element` if an app which delegates events contains the
+/// element. Returns `None` if the listener has to be attached to the element directly, e.g.
+/// because it's rendered into a portal or the shadow root of a custom element.
+pub(crate) fn register(element: &Element, listener: &Rc<dyn Listener>) -> Option<Delegated> {
+    let name = listener.delegated_event()?;
+    REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        // Nested apps dispatch the events of their own elements
+        let root = registry
+            .roots
+            .iter()
+            .map(|(root, _)| root)
+            .filter(|root| root.contains(Some(element)))
+            .fold(None, |nearest: Option<&Element>, root| match nearest {
+                Some(nearest) if !nearest.contains(Some(root)) => Some(nearest),
+                _ => Some(root),
+            })?
+            .clone();
+
+        if !registry.events.contains(&name) {
+            for (root, delegates) in registry.roots.iter_mut() {
+                delegates.extend(delegate(root, name));
+            }
+            registry.events.push(name);
+        }
+
+        let id = match element_id(element) {
+            Some(id) => id,
+            None => {
+                let id = registry.next_id();
+                js_sys::Reflect::set(element, &JsValue::from_str(ID_PROPERTY), &id.into())
+                    .expect("can't set listener id of element");
+                id
+            }
+        };
+        let registration = registry.next_id();
+        registry
+            .listeners
+            .entry((id, name))
+            .or_insert_with(Vec::new)
+            .push((registration, listener.clone(), root));
+        Some(Delegated {
+            key: (id, name),
+            registration,
+        })
+    })
+}
+
+/// Returns the listeners for events named `name` which `root` dispatches to `element`.
+fn find(root: &Element, element: &Element, name: &'static str) -> Vec<Rc<dyn Listener>> {
+    let id = match element_id(element) {
+        Some(id) => id,
+        None => return Vec::new(),
+    };
+    REGISTRY.with(|registry| {
+        registry
+            .borrow()
+            .listeners
+            .get(&(id, name))
+            .map(|listeners| {
+                listeners
+                    .iter()
+                    .filter(|(_, _, listener_root)| listener_root == root)
+                    .map(|(_, listener, _)| listener.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    })
+}
+
+/// Attaches listeners to `root` which dispatch events named `name` to the listeners
+/// registered for the target element or its nearest ancestor which has any.
+fn delegate(root: &Element, name: &'static str) -> Vec<EventListener> {
+    // Bubbling events are dispatched in the bubble phase, so that listeners attached to
+    // elements directly can stop them. Other events never reach the root in that phase.
+    vec![listen(root, name, false), listen(root, name, true)]
+}
+
+fn listen(root: &Element, name: &'static str, bubble_phase: bool) -> EventListener {
+    let root_element = root.clone();
+    let listener = move |event: &Event| {
+        let bubbles = event.bubbles();
+        if bubbles != bubble_phase {
+            return;
+        }
+        let mut current = event
+            .target()
+            .and_then(|target| target.dyn_into::<Element>().ok());
+        while let Some(element) = current {
+            // The listeners are collected first because they can change the registry
+            let listeners = find(&root_element, &element, name);
+            for listener in &listeners {
+                listener.handle(&element, event);
+            }
+            // Attached listeners stop the propagation of the event, so only the nearest
+            // element with listeners handles it. Events which don't bubble are only handled
+            // by their target.
+            if !listeners.is_empty() || !bubbles || element == root_element {
+                break;
+            }
+            current = element.parent_element();
+        }
+    };
+    let phase = if bubble_phase {
+        EventListenerPhase::Bubble
+    } else {
+        EventListenerPhase::Capture
+    };
+    let options = EventListenerOptions {
+        phase,
+        passive: name == "touchstart" || name == "touchmove",
+    };
+    EventListener::new_with_options(&EventTarget::from(root.clone()), name, options, listener)
+}
+
+fn element_id(element: &Element) -> Option<u32> {
+    js_sys::Reflect::get(element, &JsValue::from_str(ID_PROPERTY))
+        .ok()
+        .and_then(|id| id.as_f64())
+        .map(|id| id as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::{onclick, CustomListener, RawEvent};
+    use crate::utils::document;
+    use crate::Callback;
+    use std::cell::Cell;
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+    use web_sys::EventInit;
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[test]
+    fn dispatches_to_registered_listener() {
+        let root = document().create_element("div").unwrap();
+        let button = document().create_element("button").unwrap();
+        root.append_child(&button).unwrap();
+        add_root(root.clone());
+
+        let clicks = Rc::new(Cell::new(0));
+        let listener: Rc<dyn Listener> = {
+            let clicks = clicks.clone();
+            let callback = Callback::from(move |_| clicks.set(clicks.get() + 1));
+            Rc::new(onclick::Wrapper::new(callback))
+        };
+        let delegated = register(&button, &listener).unwrap();
+
+        button
+            .dispatch_event(&Event::new("click").unwrap())
+            .unwrap();
+        assert_eq!(clicks.get(), 1);
+
+        drop(delegated);
+        button
+            .dispatch_event(&Event::new("click").unwrap())
+            .unwrap();
+        assert_eq!(clicks.get(), 1);
+        remove_root(&root);
+    }
+
+    #[test]
+    fn dispatches_to_listeners_of_same_event() {
+        let root = document().create_element("div").unwrap();
+        let button = document().create_element("button").unwrap();
+        root.append_child(&button).unwrap();
+        add_root(root.clone());
+
+        let clicks = Rc::new(Cell::new(0));
+        let wrapper: Rc<dyn Listener> = {
+            let clicks = clicks.clone();
+            let callback = Callback::from(move |_| clicks.set(clicks.get() + 1));
+            Rc::new(onclick::Wrapper::new(callback))
+        };
+        let custom: Rc<dyn Listener> = {
+            let clicks = clicks.clone();
+            let callback = Callback::from(move |_: RawEvent| clicks.set(clicks.get() + 10));
+            Rc::new(CustomListener::new("click", callback))
+        };
+        let _wrapper = register(&button, &wrapper).unwrap();
+        let custom = register(&button, &custom).unwrap();
+
+        button
+            .dispatch_event(&Event::new("click").unwrap())
+            .unwrap();
+        assert_eq!(clicks.get(), 11);
+
+        drop(custom);
+        button
+            .dispatch_event(&Event::new("click").unwrap())
+            .unwrap();
+        assert_eq!(clicks.get(), 12);
+        remove_root(&root);
+    }
+
+    #[test]
+    fn dispatches_to_nearest_ancestor_with_listeners() {
+        let root = document().create_element("div").unwrap();
+        let list = document().create_element("ul").unwrap();
+        let item = document().create_element("li").unwrap();
+        let label = document().create_element("span").unwrap();
+        root.append_child(&list).unwrap();
+        list.append_child(&item).unwrap();
+        item.append_child(&label).unwrap();
+        add_root(root.clone());
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let item_listener: Rc<dyn Listener> = {
+            let log = log.clone();
+            let callback = Callback::from(move |_: RawEvent| log.borrow_mut().push("li"));
+            Rc::new(CustomListener::new("click", callback))
+        };
+        let list_listener: Rc<dyn Listener> = {
+            let log = log.clone();
+            let callback = Callback::from(move |_: RawEvent| log.borrow_mut().push("ul"));
+            Rc::new(CustomListener::new("click", callback))
+        };
+        let _item = register(&item, &item_listener).unwrap();
+        let _list = register(&list, &list_listener).unwrap();
+
+        let bubbling =
+            || Event::new_with_event_init_dict("click", EventInit::new().bubbles(true)).unwrap();
+        // Like with attached listeners, the ancestors of the handling element don't get it
+        label.dispatch_event(&bubbling()).unwrap();
+        assert_eq!(*log.borrow(), vec!["li"]);
+
+        log.borrow_mut().clear();
+        list.dispatch_event(&bubbling()).unwrap();
+        assert_eq!(*log.borrow(), vec!["ul"]);
+
+        // Events which don't bubble only reach their target
+        log.borrow_mut().clear();
+        label.dispatch_event(&Event::new("click").unwrap()).unwrap();
+        item.dispatch_event(&Event::new("click").unwrap()).unwrap();
+        assert_eq!(*log.borrow(), vec!["li"]);
+        remove_root(&root);
+    }
+
+    #[test]
+    fn delegates_only_inside_of_roots() {
+        let root = document().create_element("div").unwrap();
+        let app = document().create_element("div").unwrap();
+        let button = document().create_element("button").unwrap();
+        let outside = document().create_element("button").unwrap();
+        root.append_child(&app).unwrap();
+        app.append_child(&button).unwrap();
+        add_root(root.clone());
+        add_root(app.clone());
+
+        let clicks = Rc::new(Cell::new(0));
+        let listener: Rc<dyn Listener> = {
+            let clicks = clicks.clone();
+            let callback = Callback::from(move |_: RawEvent| clicks.set(clicks.get() + 1));
+            Rc::new(CustomListener::new("click", callback))
+        };
+        assert!(register(&outside, &listener).is_none());
+
+        // Only the nearest root dispatches to the listener
+        let _delegated = register(&button, &listener).unwrap();
+        let event = Event::new_with_event_init_dict("click", EventInit::new().bubbles(true));
+        button.dispatch_event(&event.unwrap()).unwrap();
+        assert_eq!(clicks.get(), 1);
+
+        remove_root(&app);
+        remove_root(&root);
+        button
+            .dispatch_event(&Event::new("click").unwrap())
+            .unwrap();
+        assert_eq!(clicks.get(), 1);
+    }
+}
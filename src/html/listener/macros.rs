@@ -12,13 +12,17 @@ macro_rules! impl_action {
             use crate::callback::Callback;
             #[allow(unused_imports)]
             use crate::html::listener::*;
-            use crate::virtual_dom::Listener;
+            use crate::virtual_dom::{vtag, Browser, Listener, ListenerOptions};
+            use std::cell::RefCell;
+            use std::rc::Rc;
             cfg_if! {
                 if #[cfg(feature = "std_web")] {
                     use stdweb::web::event::{$type, IEvent};
                     use stdweb::web::{Element, IEventTarget};
+                    #[allow(unused_imports)]
+                    use stdweb::{_js_impl, js};
                 } else if #[cfg(feature = "web_sys")] {
-                    use gloo::events::{EventListener, EventListenerOptions};
+                    use gloo::events::{EventListener, EventListenerOptions, EventListenerPhase};
                     use wasm_bindgen::JsValue;
                     use web_sys::{$type as WebSysType, Element, EventTarget};
                 }
@@ -27,13 +31,20 @@ macro_rules! impl_action {
             /// A wrapper for a callback which attaches event listeners to elements.
             #[derive(Clone, Debug)]
             pub struct Wrapper {
-                callback: Callback<Event>,
+                /// Shared with the attached listener, so that the callback can be swapped.
+                callback: Rc<RefCell<Callback<Event>>>,
+                options: Option<ListenerOptions>,
             }
 
             impl Wrapper {
                 /// Create a wrapper for an event-typed callback
                 pub fn new(callback: Callback<Event>) -> Self {
-                    Wrapper { callback }
+                    Wrapper { callback: Rc::new(RefCell::new(callback)), options: None }
+                }
+
+                /// Create a wrapper for an event-typed callback which is attached with `options`
+                pub fn with_options(callback: Callback<Event>, options: ListenerOptions) -> Self {
+                    Wrapper { callback: Rc::new(RefCell::new(callback)), options: Some(options) }
                 }
             }
 
@@ -45,28 +56,84 @@ macro_rules! impl_action {
                     stringify!($action)
                 }
 
+                fn take_callback(&self, other: &dyn Listener) -> bool {
+                    match other.as_any().downcast_ref::<Wrapper>() {
+                        Some(other) if self.options == other.options => {
+                            let callback = other.callback.borrow().clone();
+                            *self.callback.borrow_mut() = callback;
+                            true
+                        }
+                        _ => false,
+                    }
+                }
+
+                fn options(&self) -> Option<ListenerOptions> {
+                    self.options
+                }
+
+                #[cfg(feature = "web_sys")]
+                fn delegated_event(&self) -> Option<&'static str> {
+                    Some($name)
+                }
+
+                #[cfg(feature = "web_sys")]
+                fn handle(&self, element: &Element, event: &web_sys::Event) {
+                    let event: WebSysType = JsValue::from(event).into();
+                    // The callback can be swapped by a render the callback triggers
+                    let callback = self.callback.borrow().clone();
+                    callback.emit($convert(element, event));
+                    vtag::restore_controlled(&Browser, element);
+                }
+
                 fn attach(&self, element: &Element) -> EventListener {
                     let this = element.clone();
                     let callback = self.callback.clone();
+                    // A capturing listener must not stop the event before it reaches the target
+                    let capture = self.options.map_or(false, |options| options.capture);
                     let listener = move |
                         #[cfg(feature = "std_web")] event: $type,
                         #[cfg(feature = "web_sys")] event: &web_sys::Event
                     | {
-                        event.stop_propagation();
+                        if !capture {
+                            event.stop_propagation();
+                        }
                         #[cfg(feature = "web_sys")]
                         let event: WebSysType = JsValue::from(event).into();
+                        // The callback can be swapped by a render the callback triggers
+                        let callback = callback.borrow().clone();
                         callback.emit($convert(&this, event));
+                        vtag::restore_controlled(&Browser, &this);
                     };
                     cfg_match! {
-                        feature = "std_web" => EventListener(Some(element.add_event_listener(listener))),
+                        feature = "std_web" => match self.options {
+                            Some(options) => EventListener::with_options(element, listener, options),
+                            None => EventListener::new(element.add_event_listener(listener)),
+                        },
                         feature = "web_sys" => ({
-                            // We should only set passive event listeners for `touchstart` and `touchmove`.
-                            // See here: https://developer.mozilla.org/en-US/docs/Web/API/EventTarget/addEventListener#Improving_scrolling_performance_with_passive_listeners
-                            if $name == "touchstart" || $name == "touchmove" {
-                                EventListener::new(&EventTarget::from(element.clone()), $name, listener)
-                            } else {
-                                let options = EventListenerOptions::enable_prevent_default();
-                                EventListener::new_with_options(&EventTarget::from(element.clone()), $name, options, listener)
+                            let target = EventTarget::from(element.clone());
+                            match self.options {
+                                Some(options) => {
+                                    let phase = if options.capture {
+                                        EventListenerPhase::Capture
+                                    } else {
+                                        EventListenerPhase::Bubble
+                                    };
+                                    let gloo_options = EventListenerOptions { phase, passive: options.passive };
+                                    if options.once {
+                                        EventListener::once_with_options(&target, $name, gloo_options, listener)
+                                    } else {
+                                        EventListener::new_with_options(&target, $name, gloo_options, listener)
+                                    }
+                                }
+                                // We should only set passive event listeners for `touchstart` and `touchmove`.
+                                // See here: https://developer.mozilla.org/en-US/docs/Web/API/EventTarget/addEventListener#Improving_scrolling_performance_with_passive_listeners
+                                None if $name == "touchstart" || $name == "touchmove" => {
+                                    EventListener::new(&target, $name, listener)
+                                }
+                                None => {
+                                    let options = EventListenerOptions::enable_prevent_default();
+                                    EventListener::new_with_options(&target, $name, options, listener)
+                                }
                             }
                         }),
                     }
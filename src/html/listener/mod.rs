@@ -1,20 +1,28 @@
 #[macro_use]
 mod macros;
+mod custom;
 
+use crate::virtual_dom::Listener;
 use cfg_if::cfg_if;
 use cfg_match::cfg_match;
+use std::rc::Rc;
+
+pub use custom::{CustomListener, FromEvent, RawEvent};
 
 cfg_if! {
     if #[cfg(feature = "std_web")] {
         mod listener_stdweb;
 
-        use stdweb::js;
+        use crate::virtual_dom::ListenerOptions;
+        use stdweb::{js, Mut, Reference, Value};
         use stdweb::unstable::{TryFrom, TryInto};
+        use stdweb::web::event::ConcreteEvent;
         use stdweb::web::html_element::{InputElement, SelectElement, TextAreaElement};
         use stdweb::web::{Element, EventListenerHandle, FileList, IElement, INode};
 
         pub use listener_stdweb::*;
     } else if #[cfg(feature = "web_sys")] {
+        pub(crate) mod delegation;
         mod listener_web_sys;
 
         use wasm_bindgen::JsCast;
@@ -23,10 +31,39 @@ cfg_if! {
             HtmlTextAreaElement as TextAreaElement,
         };
 
+        use gloo::events::EventListener;
+
         pub use listener_web_sys::*;
     }
 }
 
+/// Keeps a listener of an element active until it's dropped.
+// The handles are never read, only dropped
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) enum ListenerHandle {
+    /// The listener is attached to the element itself.
+    Attached(EventListener),
+    /// The listener is called by the root of the app.
+    #[cfg(feature = "web_sys")]
+    Delegated(delegation::Delegated),
+}
+
+/// Activates `listener` for `element`, either directly or through the root of the app if
+/// it delegates events.
+pub(crate) fn activate_listener(listener: &Rc<dyn Listener>, element: &Element) -> ListenerHandle {
+    // The root can't honor the options of a listener
+    #[cfg(feature = "web_sys")]
+    {
+        if listener.options().is_none() {
+            if let Some(delegated) = delegation::register(element, listener) {
+                return ListenerHandle::Delegated(delegated);
+            }
+        }
+    }
+    ListenerHandle::Attached(listener.attach(element))
+}
+
 /// A type representing data from `oninput` event.
 #[derive(Debug)]
 pub struct InputData {
@@ -133,13 +170,96 @@ fn onchange_handler(this: &Element) -> ChangeData {
 /// Handler to an event listener, only use is to cancel the event.
 #[cfg(feature = "std_web")]
 #[derive(Debug)]
-pub struct EventListener(Option<EventListenerHandle>);
+pub struct EventListener(Option<EventListenerKind>);
+
+#[cfg(feature = "std_web")]
+#[derive(Debug)]
+enum EventListenerKind {
+    /// A listener added by `stdweb`.
+    Default(EventListenerHandle),
+    /// A listener added with options or for an event by name, which `stdweb` doesn't
+    /// support.
+    WithOptions {
+        element: Element,
+        kind: &'static str,
+        listener: Value,
+        capture: bool,
+    },
+}
+
+#[cfg(feature = "std_web")]
+impl EventListener {
+    pub(crate) fn new(handle: EventListenerHandle) -> Self {
+        EventListener(Some(EventListenerKind::Default(handle)))
+    }
+
+    pub(crate) fn with_options<T, F>(
+        element: &Element,
+        listener: F,
+        options: ListenerOptions,
+    ) -> Self
+    where
+        T: ConcreteEvent,
+        F: FnMut(T) + 'static,
+    {
+        let ListenerOptions {
+            passive,
+            capture,
+            once,
+        } = options;
+        let listener = js! {
+            var listener = @{Mut(listener)};
+            @{element}.addEventListener(@{T::EVENT_TYPE}, listener, {
+                passive: @{passive},
+                capture: @{capture},
+                once: @{once},
+            });
+            return listener;
+        };
+        EventListener(Some(EventListenerKind::WithOptions {
+            element: element.clone(),
+            kind: T::EVENT_TYPE,
+            listener,
+            capture,
+        }))
+    }
+
+    pub(crate) fn with_kind<F>(element: &Element, kind: &'static str, listener: F) -> Self
+    where
+        F: FnMut(Reference) + 'static,
+    {
+        let listener = js! {
+            var listener = @{Mut(listener)};
+            @{element}.addEventListener(@{kind}, listener);
+            return listener;
+        };
+        EventListener(Some(EventListenerKind::WithOptions {
+            element: element.clone(),
+            kind,
+            listener,
+            capture: false,
+        }))
+    }
+}
 
 #[cfg(feature = "std_web")]
 impl Drop for EventListener {
     fn drop(&mut self) {
-        if let Some(event) = self.0.take() {
-            event.remove()
+        match self.0.take() {
+            Some(EventListenerKind::Default(handle)) => handle.remove(),
+            Some(EventListenerKind::WithOptions {
+                element,
+                kind,
+                listener,
+                capture,
+            }) => {
+                js! { @(no_return)
+                    var listener = @{listener};
+                    @{element}.removeEventListener(@{kind}, listener, @{capture});
+                    listener.drop();
+                }
+            }
+            None => {}
         }
     }
 }
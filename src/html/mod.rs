@@ -3,15 +3,18 @@
 //! Also this module contains declaration of `Component` trait which used
 //! to create own UI-components.
 
+mod context;
 mod listener;
 mod scope;
 
+pub use context::ContextHandle;
+pub(crate) use context::{ContextState, SharedContext};
 pub use listener::*;
-pub use scope::Scope;
+pub use scope::{ComponentError, Scope, Suspension};
 pub(crate) use scope::{ComponentUpdate, HiddenScope};
 
 use crate::callback::Callback;
-use crate::virtual_dom::{VChild, VList, VNode};
+use crate::virtual_dom::{Handle, VChild, VList, VNode};
 use cfg_if::cfg_if;
 use cfg_match::cfg_match;
 use std::any::TypeId;
@@ -58,8 +61,18 @@ pub trait Component: Sized + 'static {
     }
     /// Called by rendering loop.
     fn view(&self) -> Html;
+    /// Called after every time the view was applied to the DOM, once the components created
+    /// by it are created and mounted and the other children are updated as well, so children
+    /// are called before their parent. `first_render` is true after the first time, which is
+    /// after `mounted`.
+    /// Use it to measure the layout or to update widgets which are not managed by yew.
+    fn rendered(&mut self, _first_render: bool) {}
     /// Called for finalization on the final point of the component's lifetime.
-    fn destroy(&mut self) {} // TODO(#941): Replace with `Drop`
+    ///
+    /// Components are dropped once they are destroyed: after their children and after their
+    /// nodes are removed from the DOM. Implement `Drop` to clean up instead.
+    #[deprecated(note = "implement `Drop` for the component instead")]
+    fn destroy(&mut self) {}
 }
 
 /// A type which expected as a result of `view` function implementation.
@@ -326,15 +339,20 @@ pub struct NodeRef(Rc<RefCell<NodeRefInner>>);
 
 #[derive(PartialEq, Debug, Default, Clone)]
 struct NodeRefInner {
-    node: Option<Node>,
+    node: Option<Handle<Node>>,
     link: Option<NodeRef>,
 }
 
 impl NodeRef {
     /// Get the wrapped Node reference if it exists
     pub fn get(&self) -> Option<Node> {
+        self.handle()?.browser().cloned()
+    }
+
+    /// Returns the node of any backend, which is wrapped by the reference.
+    pub(crate) fn handle(&self) -> Option<Handle<Node>> {
         let inner = self.0.borrow();
-        inner.node.clone().or_else(|| inner.link.as_ref()?.get())
+        inner.node.clone().or_else(|| inner.link.as_ref()?.handle())
     }
 
     /// Try converting the node reference into another form
@@ -352,7 +370,7 @@ impl NodeRef {
     }
 
     /// Place a Node in a reference for later use
-    pub(crate) fn set(&self, node: Option<Node>) {
+    pub(crate) fn set(&self, node: Option<Handle<Node>>) {
         self.0.borrow_mut().node = node;
     }
 
@@ -360,6 +378,16 @@ impl NodeRef {
     pub(crate) fn link(&self, node_ref: Self) {
         self.0.borrow_mut().link = Some(node_ref);
     }
+
+    /// Takes the node and the link of `other`, which is replaced by this reference.
+    pub(crate) fn replace(&self, other: &NodeRef) {
+        if !Rc::ptr_eq(&self.0, &other.0) {
+            let other = other.0.borrow();
+            let mut inner = self.0.borrow_mut();
+            inner.node = other.node.clone();
+            inner.link = other.link.clone();
+        }
+    }
 }
 
 /// Trait for rendering virtual DOM elements
@@ -1,9 +1,14 @@
 use super::*;
+use crate::executor::Spawned;
 use crate::scheduler::{scheduler, Runnable, Shared};
-use crate::virtual_dom::{VDiff, VNode};
+use crate::virtual_dom::{Handle, VNode, VRender};
 use cfg_if::cfg_if;
+use log::error;
+use std::any::{self, Any, TypeId};
 use std::cell::RefCell;
 use std::fmt;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 cfg_if! {
     if #[cfg(feature = "std_web")] {
@@ -13,6 +18,106 @@ cfg_if! {
     }
 }
 
+thread_local! {
+    /// The boundaries of components which get mounted while a component applies its view.
+    static BOUNDARIES: RefCell<Boundaries> = RefCell::new(Boundaries::default());
+}
+
+/// Components which handle panics and suspensions of their descendants or provide contexts
+/// to them.
+#[derive(Clone, Default)]
+struct Boundaries {
+    error: Option<Callback<ComponentError>>,
+    suspense: Option<Callback<bool>>,
+    /// Provided contexts by the type of their value, the closest last.
+    contexts: Vec<(TypeId, Rc<dyn Any>)>,
+}
+
+impl Boundaries {
+    /// Returns the boundaries of `self` falling back to the ones of `outer`.
+    fn or(&self, outer: &Boundaries) -> Boundaries {
+        Boundaries {
+            error: self.error.clone().or_else(|| outer.error.clone()),
+            suspense: self.suspense.clone().or_else(|| outer.suspense.clone()),
+            contexts: outer
+                .contexts
+                .iter()
+                .chain(self.contexts.iter())
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// A panic of a component which was caught by an error boundary, or an error it failed with.
+///
+/// See [`Scope::catch_errors`](struct.Scope.html#method.catch_errors) and
+/// [`Scope::fail`](struct.Scope.html#method.fail).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentError {
+    /// Type name of the component which panicked.
+    pub component: &'static str,
+    /// Message of the panic or the error.
+    pub message: String,
+}
+
+impl ComponentError {
+    fn new<COMP: Component>(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<Any>".to_string()
+        };
+        ComponentError {
+            component: any::type_name::<COMP>(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ComponentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "component {} failed: {}", self.component, self.message)
+    }
+}
+
+/// A handle which keeps the closest suspense boundary of a component suspended.
+///
+/// The boundary shows its fallback until all of its suspensions are resumed or dropped.
+/// See [`Scope::suspend`](struct.Scope.html#method.suspend).
+#[must_use = "the suspension is resumed when dropped"]
+pub struct Suspension {
+    boundary: Option<Callback<bool>>,
+}
+
+impl Suspension {
+    fn new(boundary: Option<Callback<bool>>) -> Self {
+        if let Some(boundary) = &boundary {
+            boundary.emit(true);
+        }
+        Suspension { boundary }
+    }
+
+    /// Tells the boundary that the component is ready.
+    pub fn resume(self) {}
+}
+
+impl Drop for Suspension {
+    fn drop(&mut self) {
+        if let Some(boundary) = self.boundary.take() {
+            boundary.emit(false);
+        }
+    }
+}
+
+impl fmt::Debug for Suspension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Suspension")
+    }
+}
+
 /// Updates for a `Component` instance. Used by scope sender.
 pub(crate) enum ComponentUpdate<COMP: Component> {
     /// Wraps messages for a component.
@@ -21,11 +126,19 @@ pub(crate) enum ComponentUpdate<COMP: Component> {
     MessageBatch(Vec<COMP::Message>),
     /// Wraps properties for a component.
     Properties(COMP::Properties),
+    /// Fails a component with an error.
+    Failure(ComponentError),
 }
 
 /// A context which allows sending messages to a component.
 pub struct Scope<COMP: Component> {
     shared_state: Shared<ComponentState<COMP>>,
+    /// Boundaries of the ancestors of the component.
+    boundaries: Shared<Boundaries>,
+    /// Boundaries which the component provides to its descendants.
+    provided: Shared<Boundaries>,
+    /// Futures which are dropped when the component is destroyed.
+    futures: Shared<Vec<Rc<Spawned>>>,
 }
 
 impl<COMP: Component> fmt::Debug for Scope<COMP> {
@@ -38,6 +151,9 @@ impl<COMP: Component> Clone for Scope<COMP> {
     fn clone(&self) -> Self {
         Scope {
             shared_state: self.shared_state.clone(),
+            boundaries: self.boundaries.clone(),
+            provided: self.provided.clone(),
+            futures: self.futures.clone(),
         }
     }
 }
@@ -52,18 +168,28 @@ impl<COMP: Component> Scope<COMP> {
     /// visible for testing
     pub fn new() -> Self {
         let shared_state = Rc::new(RefCell::new(ComponentState::Empty));
-        Scope { shared_state }
+        let boundaries = Rc::new(RefCell::new(Boundaries::default()));
+        let provided = Rc::new(RefCell::new(Boundaries::default()));
+        let futures = Rc::new(RefCell::new(Vec::new()));
+        Scope {
+            shared_state,
+            boundaries,
+            provided,
+            futures,
+        }
     }
 
-    /// Mounts a component with `props` to the specified `element` in the DOM.
+    /// Mounts a component with `props` to the specified `element` in the DOM. The component
+    /// is rendered with the backend which created the element.
     pub(crate) fn mount_in_place(
         self,
-        element: Element,
+        element: Handle<Element>,
         ancestor: Option<VNode>,
         node_ref: NodeRef,
         props: COMP::Properties,
     ) -> Scope<COMP> {
         let mut scope = self;
+        *scope.boundaries.borrow_mut() = BOUNDARIES.with(|boundaries| boundaries.borrow().clone());
         let ready_state = ReadyState {
             element,
             node_ref,
@@ -73,22 +199,37 @@ impl<COMP: Component> Scope<COMP> {
         };
         *scope.shared_state.borrow_mut() = ComponentState::Ready(ready_state);
         scope.create();
-        scope.mounted();
         scope
     }
 
-    /// Schedules a task to call the mounted method on a component and optionally re-render
-    pub(crate) fn mounted(&mut self) {
-        let shared_state = self.shared_state.clone();
-        let mounted = MountedComponent { shared_state };
-        scheduler().push_mount(Box::new(mounted));
+    /// Creates a component with `props` and renders its view to `html` without mounting it to
+    /// the DOM. The children of the component see the contexts it provides.
+    ///
+    /// The component never gets mounted, so any messages sent to it are ignored.
+    pub(crate) fn render_html(self, props: COMP::Properties, html: &mut String) {
+        *self.boundaries.borrow_mut() = BOUNDARIES.with(|boundaries| boundaries.borrow().clone());
+        *self.shared_state.borrow_mut() = ComponentState::Destroyed;
+        let scope = self.clone();
+        let mut component = COMP::create(props, self);
+        let root = component.render();
+        let boundaries = scope.provided.borrow().or(&scope.boundaries.borrow());
+        let outer = BOUNDARIES.with(|current| current.replace(boundaries));
+        root.render_html(html);
+        BOUNDARIES.with(|current| *current.borrow_mut() = outer);
+        #[allow(deprecated)]
+        component.destroy();
     }
 
-    /// Schedules a task to create and render a component and then mount it to the DOM
+    /// Schedules a task to create and render a component and then mount it to the DOM, and
+    /// a task to call the mounted method on it and optionally re-render
     pub(crate) fn create(&mut self) {
-        let shared_state = self.shared_state.clone();
-        let create = CreateComponent { shared_state };
-        scheduler().push_create(Box::new(create));
+        let create = CreateComponent {
+            shared_state: self.shared_state.clone(),
+        };
+        let mounted = MountedComponent {
+            shared_state: self.shared_state.clone(),
+        };
+        scheduler().push_create_and_mount(Box::new(create), Box::new(mounted));
     }
 
     /// Schedules a task to send a message or new props to a component
@@ -100,11 +241,204 @@ impl<COMP: Component> Scope<COMP> {
         scheduler().push(Box::new(update));
     }
 
-    /// Schedules a task to destroy a component
+    /// Sends new properties to a component which is kept by a new `VComp`. The root node of
+    /// the component is kept in `node_ref` from now on, instead of the reference of the
+    /// previous `VComp`.
+    pub(crate) fn reuse(&self, props: COMP::Properties, node_ref: NodeRef) {
+        match &mut *self.shared_state.borrow_mut() {
+            ComponentState::Ready(state) => {
+                node_ref.replace(&state.node_ref);
+                state.node_ref = node_ref;
+            }
+            ComponentState::Created(state) => {
+                node_ref.replace(&state.node_ref);
+                state.node_ref = node_ref;
+            }
+            _ => {}
+        }
+        self.update(ComponentUpdate::Properties(props));
+    }
+
+    /// Returns the top level DOM nodes of the last view of the component, or `None` if it
+    /// wasn't rendered yet.
+    pub(crate) fn dom_nodes(&self) -> Option<Vec<Handle<Node>>> {
+        match &*self.shared_state.borrow() {
+            ComponentState::Created(state) => state.last_frame.as_ref().map(VNode::dom_nodes),
+            _ => None,
+        }
+    }
+
+    /// Moves the DOM nodes of the component to `parent`, in front of `next_sibling`. The
+    /// component renders into the new parent from now on.
+    pub(crate) fn shift(&self, parent: Handle<Element>, next_sibling: Option<Handle<Node>>) {
+        match &mut *self.shared_state.borrow_mut() {
+            ComponentState::Ready(state) => {
+                // Until the first render the ancestor stakes out the place of the component
+                if let Some(ancestor) = &state.ancestor {
+                    ancestor.shift_to(&parent, next_sibling.as_ref());
+                }
+                state.element = parent;
+            }
+            ComponentState::Created(state) => {
+                if let Some(last_frame) = &state.last_frame {
+                    last_frame.shift_to(&parent, next_sibling.as_ref());
+                }
+                state.element = parent;
+            }
+            _ => {}
+        }
+    }
+
+    /// Destroys a component: its children are destroyed first, then its nodes are removed
+    /// from the DOM and then the component is dropped.
     pub(crate) fn destroy(&mut self) {
+        for future in self.futures.borrow_mut().drain(..) {
+            future.cancel();
+        }
         let shared_state = self.shared_state.clone();
-        let destroy = DestroyComponent { shared_state };
-        scheduler().push(Box::new(destroy));
+        let destroy = Box::new(DestroyComponent { shared_state });
+        // A component which is being processed is destroyed right after
+        if let ComponentState::Processing = *self.shared_state.borrow() {
+            scheduler().push(destroy);
+            return;
+        }
+        destroy.run();
+    }
+
+    /// Makes the component an error boundary of its descendants.
+    ///
+    /// When a component rendered inside of this one panics in any of its methods, its
+    /// subtree is removed from the DOM, the component is dropped without `destroy` and the
+    /// error is sent to `callback`. Panics of this component go to its own boundary.
+    ///
+    /// Panics can only be caught if the target unwinds on panic. With `panic = "abort"`,
+    /// which is the only strategy of `wasm32-unknown-unknown`, the application is aborted as
+    /// usual. Components can report errors to the boundary with [`fail`](#method.fail)
+    /// instead, which works on every target.
+    pub fn catch_errors(&self, callback: Callback<ComponentError>) {
+        self.provided.borrow_mut().error = Some(callback);
+    }
+
+    /// Fails the component without panicking: its subtree is removed from the DOM, the
+    /// component is dropped without `destroy` and `error` is sent to the closest error
+    /// boundary, just like after a caught panic. Without a boundary the error is logged.
+    pub fn fail<E: fmt::Display>(&self, error: E) {
+        let error = ComponentError {
+            component: any::type_name::<COMP>(),
+            message: error.to_string(),
+        };
+        self.update(ComponentUpdate::Failure(error));
+    }
+
+    /// Makes the component a suspense boundary of its descendants.
+    ///
+    /// `callback` gets `true` when a descendant suspends and `false` when a suspension is
+    /// resumed.
+    pub(crate) fn catch_suspensions(&self, callback: Callback<bool>) {
+        self.provided.borrow_mut().suspense = Some(callback);
+    }
+
+    /// Provides `context` to the descendants of the component.
+    pub(crate) fn provide_context<T: 'static>(&self, context: SharedContext<T>) {
+        self.provided
+            .borrow_mut()
+            .contexts
+            .push((TypeId::of::<T>(), context));
+    }
+
+    /// Returns the value of the context of type `T` provided by the closest ancestor, see
+    /// [`ContextProvider`](../components/context/struct.ContextProvider.html). `callback`
+    /// gets the new value whenever it changes, until the returned handle is dropped.
+    ///
+    /// Returns `None` if no ancestor provides a context of type `T`.
+    pub fn context<T: Clone + 'static>(
+        &self,
+        callback: Callback<T>,
+    ) -> Option<(T, ContextHandle<T>)> {
+        let context = self
+            .boundaries
+            .borrow()
+            .contexts
+            .iter()
+            .rev()
+            .find(|(type_id, _)| *type_id == TypeId::of::<T>())
+            .map(|(_, context)| context.clone())?;
+        let context = context.downcast::<RefCell<ContextState<T>>>().ok()?;
+        Some(ContextState::subscribe(&context, callback))
+    }
+
+    /// Suspends the closest suspense boundary of the component until the returned
+    /// `Suspension` is resumed or dropped.
+    ///
+    /// Without a suspense boundary it does nothing.
+    pub fn suspend(&self) -> Suspension {
+        Suspension::new(self.boundaries.borrow().suspense.clone())
+    }
+
+    /// Suspends the closest suspense boundary until `future` is completed and
+    /// sends its output to the component.
+    ///
+    /// The message is sent before the suspension is resumed, so the component can render
+    /// the loaded data before it's shown.
+    pub fn suspend_on<F>(&self, future: F)
+    where
+        F: Future<Output = COMP::Message> + 'static,
+    {
+        let suspension = self.suspend();
+        let scope = self.clone();
+        self.spawn(async move {
+            let message = future.await;
+            scope.send_message(message);
+            suspension.resume();
+        });
+    }
+
+    /// Runs `future` and sends its output to the component.
+    ///
+    /// The future is dropped if the component is destroyed before it completes, or right
+    /// away if it is rendered to a string.
+    pub fn send_future<F, M>(&self, future: F)
+    where
+        M: Into<COMP::Message>,
+        F: Future<Output = M> + 'static,
+    {
+        let scope = self.clone();
+        self.spawn(async move {
+            let message = future.await;
+            scope.send_message(message);
+        });
+    }
+
+    /// This method creates a `Callback` which runs the future returned by `function` and
+    /// sends its output to the linked component's update method.
+    ///
+    /// The futures are dropped if the component is destroyed before they complete.
+    pub fn callback_future<F, FU, IN, M>(&self, function: F) -> Callback<IN>
+    where
+        M: Into<COMP::Message>,
+        FU: Future<Output = M> + 'static,
+        F: Fn(IN) -> FU + 'static,
+    {
+        let scope = self.clone();
+        let closure = move |input| {
+            scope.send_future(function(input));
+        };
+        closure.into()
+    }
+
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        // Destroyed components, like those rendered to a string, drop their futures
+        if let ComponentState::Destroyed = *self.shared_state.borrow() {
+            return;
+        }
+        // The future can be polled right away and spawn other futures of the component
+        let spawned = Spawned::spawn(future);
+        let mut futures = self.futures.borrow_mut();
+        futures.retain(|future| !future.is_done());
+        futures.push(spawned);
     }
 
     /// Send a message to the component
@@ -171,7 +505,7 @@ impl<COMP: Component> fmt::Display for ComponentState<COMP> {
 }
 
 struct ReadyState<COMP: Component> {
-    element: Element,
+    element: Handle<Element>,
     node_ref: NodeRef,
     props: COMP::Properties,
     scope: Scope<COMP>,
@@ -179,36 +513,132 @@ struct ReadyState<COMP: Component> {
 }
 
 impl<COMP: Component> ReadyState<COMP> {
-    fn create(self) -> CreatedState<COMP> {
-        CreatedState {
-            component: COMP::create(self.props, self.scope),
-            element: self.element,
-            last_frame: self.ancestor,
-            node_ref: self.node_ref,
+    fn create(self) -> ComponentState<COMP> {
+        let ReadyState {
+            element,
+            node_ref,
+            props,
+            scope,
+            ancestor,
+        } = self;
+        let boundaries = scope.boundaries.clone();
+        let provided = scope.provided.clone();
+        let boundary = boundaries.borrow().error.clone();
+        match guard::<COMP, _>(&boundary, move || COMP::create(props, scope)) {
+            Ok(component) => ComponentState::Created(CreatedState {
+                element,
+                component,
+                last_frame: ancestor,
+                node_ref,
+                boundaries,
+                provided,
+                has_rendered: false,
+                pending_rendered: None,
+            }),
+            Err(error) => fail(&element, ancestor, boundary, error),
         }
     }
 }
 
 struct CreatedState<COMP: Component> {
-    element: Element,
+    element: Handle<Element>,
     component: COMP,
     last_frame: Option<VNode>,
     node_ref: NodeRef,
+    boundaries: Shared<Boundaries>,
+    provided: Shared<Boundaries>,
+    /// Set once the view of the component was applied for the first time.
+    has_rendered: bool,
+    /// Set when the view was applied and `rendered` has to be called, with the first render
+    /// flag.
+    pending_rendered: Option<bool>,
+}
+
+/// Runs code of a component. If the component has an error boundary, a panic is caught
+/// and returned as an error.
+fn guard<COMP: Component, T>(
+    boundary: &Option<Callback<ComponentError>>,
+    f: impl FnOnce() -> T,
+) -> Result<T, ComponentError> {
+    if boundary.is_none() {
+        return Ok(f());
+    }
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(ComponentError::new::<COMP>)
+}
+
+/// Removes the DOM of a failed component and reports the error to its boundary, or logs it
+/// if there is none.
+fn fail<COMP: Component>(
+    element: &Handle<Element>,
+    last_frame: Option<VNode>,
+    boundary: Option<Callback<ComponentError>>,
+    error: ComponentError,
+) -> ComponentState<COMP> {
+    if let Some(mut last_frame) = last_frame {
+        last_frame.detach_from(element);
+    }
+    match boundary {
+        Some(boundary) => boundary.emit(error),
+        None => error!("{}", error),
+    }
+    ComponentState::Destroyed
 }
 
 impl<COMP: Component> CreatedState<COMP> {
     /// Called once immediately after the component is created.
-    fn mounted(mut self) -> Self {
-        if self.component.mounted() {
-            self.update()
-        } else {
-            self
+    fn mounted(mut self) -> ComponentState<COMP> {
+        let boundary = self.error_boundary();
+        let component = &mut self.component;
+        match guard::<COMP, _>(&boundary, || component.mounted()) {
+            Ok(true) => self.update(),
+            Ok(false) => ComponentState::Created(self),
+            Err(error) => self.fail(error),
+        }
+    }
+
+    /// Applies an update to the component and re-renders it if needed.
+    fn apply_update(mut self, update: ComponentUpdate<COMP>) -> ComponentState<COMP> {
+        if let ComponentUpdate::Failure(error) = update {
+            return self.fail(error);
+        }
+        let boundary = self.error_boundary();
+        let component = &mut self.component;
+        let should_update = guard::<COMP, _>(&boundary, || match update {
+            ComponentUpdate::Message(message) => component.update(message),
+            ComponentUpdate::MessageBatch(messages) => messages
+                .into_iter()
+                .fold(false, |acc, msg| component.update(msg) || acc),
+            ComponentUpdate::Properties(props) => component.change(props),
+            ComponentUpdate::Failure(_) => unreachable!(),
+        });
+        match should_update {
+            Ok(true) => self.update(),
+            Ok(false) => ComponentState::Created(self),
+            Err(error) => self.fail(error),
         }
     }
 
-    fn update(mut self) -> Self {
-        let mut root = self.component.render();
-        if let Some(node) = root.apply(&self.element, None, self.last_frame) {
+    fn error_boundary(&self) -> Option<Callback<ComponentError>> {
+        self.boundaries.borrow().error.clone()
+    }
+
+    fn fail(self, error: ComponentError) -> ComponentState<COMP> {
+        let boundary = self.error_boundary();
+        fail(&self.element, self.last_frame, boundary, error)
+    }
+
+    fn update(mut self) -> ComponentState<COMP> {
+        let boundary = self.error_boundary();
+        let component = &self.component;
+        let mut root = match guard::<COMP, _>(&boundary, || component.render()) {
+            Ok(root) => root,
+            Err(error) => return self.fail(error),
+        };
+        let boundaries = self.provided.borrow().or(&self.boundaries.borrow());
+        let outer = BOUNDARIES.with(|current| current.replace(boundaries));
+        let node = root.apply_to(&self.element, self.last_frame.take());
+        BOUNDARIES.with(|current| *current.borrow_mut() = outer);
+        if let Some(node) = node {
             self.node_ref.set(Some(node));
         } else if let VNode::VComp(child) = &root {
             // If the root VNode is a VComp, we won't have access to the rendered DOM node
@@ -218,25 +648,55 @@ impl<COMP: Component> CreatedState<COMP> {
             self.node_ref.link(child.node_ref.clone());
         }
         self.last_frame = Some(root);
-        self
+        self.pending_rendered = Some(!self.has_rendered);
+        self.has_rendered = true;
+        ComponentState::Created(self)
+    }
+
+    /// Called after the view of the component was applied.
+    fn rendered(mut self, first_render: bool) -> ComponentState<COMP> {
+        let boundary = self.error_boundary();
+        let component = &mut self.component;
+        match guard::<COMP, _>(&boundary, || component.rendered(first_render)) {
+            Ok(()) => ComponentState::Created(self),
+            Err(error) => self.fail(error),
+        }
     }
 }
 
-struct MountedComponent<COMP>
+/// Schedules a call of the `rendered` method if the component applied its view. It runs
+/// after the components which were created while rendering are created and mounted.
+fn schedule_rendered<COMP: Component>(
+    shared_state: &Shared<ComponentState<COMP>>,
+    state: &mut ComponentState<COMP>,
+) {
+    if let ComponentState::Created(created) = state {
+        if let Some(first_render) = created.pending_rendered.take() {
+            let rendered = RenderedComponent {
+                shared_state: shared_state.clone(),
+                first_render,
+            };
+            scheduler().push_rendered(Box::new(rendered));
+        }
+    }
+}
+
+struct RenderedComponent<COMP>
 where
     COMP: Component,
 {
     shared_state: Shared<ComponentState<COMP>>,
+    first_render: bool,
 }
 
-impl<COMP> Runnable for MountedComponent<COMP>
+impl<COMP> Runnable for RenderedComponent<COMP>
 where
     COMP: Component,
 {
     fn run(self: Box<Self>) {
         let current_state = self.shared_state.replace(ComponentState::Processing);
         self.shared_state.replace(match current_state {
-            ComponentState::Created(state) => ComponentState::Created(state.mounted()),
+            ComponentState::Created(state) => state.rendered(self.first_render),
             ComponentState::Destroyed => current_state,
             ComponentState::Empty | ComponentState::Processing | ComponentState::Ready(_) => {
                 panic!("unexpected component state: {}", current_state);
@@ -245,6 +705,31 @@ where
     }
 }
 
+struct MountedComponent<COMP>
+where
+    COMP: Component,
+{
+    shared_state: Shared<ComponentState<COMP>>,
+}
+
+impl<COMP> Runnable for MountedComponent<COMP>
+where
+    COMP: Component,
+{
+    fn run(self: Box<Self>) {
+        let current_state = self.shared_state.replace(ComponentState::Processing);
+        let mut next_state = match current_state {
+            ComponentState::Created(state) => state.mounted(),
+            ComponentState::Destroyed => current_state,
+            ComponentState::Empty | ComponentState::Processing | ComponentState::Ready(_) => {
+                panic!("unexpected component state: {}", current_state);
+            }
+        };
+        schedule_rendered(&self.shared_state, &mut next_state);
+        self.shared_state.replace(next_state);
+    }
+}
+
 struct CreateComponent<COMP>
 where
     COMP: Component,
@@ -258,13 +743,18 @@ where
 {
     fn run(self: Box<Self>) {
         let current_state = self.shared_state.replace(ComponentState::Processing);
-        self.shared_state.replace(match current_state {
-            ComponentState::Ready(state) => ComponentState::Created(state.create().update()),
+        let mut next_state = match current_state {
+            ComponentState::Ready(state) => match state.create() {
+                ComponentState::Created(state) => state.update(),
+                failed => failed,
+            },
             ComponentState::Created(_) | ComponentState::Destroyed => current_state,
             ComponentState::Empty | ComponentState::Processing => {
                 panic!("unexpected component state: {}", current_state);
             }
-        });
+        };
+        schedule_rendered(&self.shared_state, &mut next_state);
+        self.shared_state.replace(next_state);
     }
 }
 
@@ -282,14 +772,18 @@ where
     fn run(self: Box<Self>) {
         match self.shared_state.replace(ComponentState::Destroyed) {
             ComponentState::Created(mut this) => {
-                this.component.destroy();
+                // Children are destroyed while their parent nodes are detached
                 if let Some(last_frame) = &mut this.last_frame {
-                    last_frame.detach(&this.element);
+                    last_frame.detach_from(&this.element);
                 }
+                #[allow(deprecated)]
+                this.component.destroy();
+                // The component is dropped once the DOM is cleaned up
+                drop(this);
             }
             ComponentState::Ready(mut this) => {
                 if let Some(ancestor) = &mut this.ancestor {
-                    ancestor.detach(&this.element);
+                    ancestor.detach_from(&this.element);
                 }
             }
             ComponentState::Empty | ComponentState::Destroyed => {}
@@ -312,23 +806,15 @@ where
 {
     fn run(self: Box<Self>) {
         let current_state = self.shared_state.replace(ComponentState::Processing);
-        self.shared_state.replace(match current_state {
-            ComponentState::Created(mut this) => {
-                let should_update = match self.update {
-                    ComponentUpdate::Message(message) => this.component.update(message),
-                    ComponentUpdate::MessageBatch(messages) => messages
-                        .into_iter()
-                        .fold(false, |acc, msg| this.component.update(msg) || acc),
-                    ComponentUpdate::Properties(props) => this.component.change(props),
-                };
-                let next_state = if should_update { this.update() } else { this };
-                ComponentState::Created(next_state)
-            }
+        let mut next_state = match current_state {
+            ComponentState::Created(this) => this.apply_update(self.update),
             ComponentState::Destroyed => current_state,
             ComponentState::Processing | ComponentState::Ready(_) | ComponentState::Empty => {
                 panic!("unexpected component state: {}", current_state);
             }
-        });
+        };
+        schedule_rendered(&self.shared_state, &mut next_state);
+        self.shared_state.replace(next_state);
     }
 }
 
@@ -360,3 +846,222 @@ impl<COMP: Component> Into<Scope<COMP>> for HiddenScope {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::virtual_dom::{DomBackend, MemoryDom, MemoryNode, VDiff};
+    use crate::{html, Properties};
+
+    struct Broken;
+
+    impl Component for Broken {
+        type Message = ();
+        type Properties = ();
+
+        fn create(_: Self::Properties, _: ComponentLink<Self>) -> Self {
+            Broken
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            false
+        }
+
+        fn view(&self) -> Html {
+            html! {}
+        }
+    }
+
+    #[test]
+    fn catches_panic_with_boundary() {
+        let boundary = Some(Callback::from(|_| ()));
+        let error = guard::<Broken, ()>(&boundary, || panic!("broken {}", 42)).unwrap_err();
+        assert!(error.component.ends_with("Broken"));
+        assert_eq!(error.message, "broken 42");
+        assert_eq!(guard::<Broken, _>(&boundary, || 42), Ok(42));
+    }
+
+    #[test]
+    fn suspension_resumes_on_drop() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let boundary = {
+            let events = events.clone();
+            Callback::from(move |suspended| events.borrow_mut().push(suspended))
+        };
+        let first = Suspension::new(Some(boundary.clone()));
+        let second = Suspension::new(Some(boundary));
+        first.resume();
+        drop(second);
+        assert_eq!(*events.borrow(), vec![true, true, false, false]);
+    }
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    #[derive(Clone, Properties)]
+    struct Props {
+        name: &'static str,
+        log: Log,
+        generation: u32,
+    }
+
+    /// Logs its lifecycle and renders two children unless it's a child itself.
+    struct Logger {
+        props: Props,
+    }
+
+    impl Logger {
+        fn log(&self, event: &str) {
+            let entry = format!("{} {}", self.props.name, event);
+            self.props.log.borrow_mut().push(entry);
+        }
+    }
+
+    impl Component for Logger {
+        type Message = ();
+        type Properties = Props;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            let logger = Logger { props };
+            logger.log("create");
+            logger
+        }
+
+        fn mounted(&mut self) -> ShouldRender {
+            self.log("mounted");
+            false
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn change(&mut self, props: Self::Properties) -> ShouldRender {
+            self.props = props;
+            true
+        }
+
+        fn rendered(&mut self, first_render: bool) {
+            self.log(&format!("rendered {}", first_render));
+        }
+
+        fn view(&self) -> Html {
+            if self.props.name != "parent" {
+                return html! { <span>{ self.props.generation }</span> };
+            }
+            let Props {
+                log, generation, ..
+            } = self.props.clone();
+            html! {
+                <div>
+                    <Logger name="a" log=log.clone() generation=generation />
+                    <Logger name="b" log=log generation=generation />
+                </div>
+            }
+        }
+    }
+
+    #[test]
+    fn calls_rendered_after_children_are_mounted() {
+        let dom = MemoryDom;
+        let parent = dom.create_element("div", None);
+        let log = Log::default();
+        let view = |generation| {
+            html! { <Logger name="parent" log=log.clone() generation=generation /> }
+        };
+
+        let mut ancestor = view(1);
+        ancestor.apply(&dom, &parent, None, None);
+        assert_eq!(parent.text_content(), "11");
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "parent create",
+                "a create",
+                "b create",
+                "b mounted",
+                "a mounted",
+                "parent mounted",
+                "b rendered true",
+                "a rendered true",
+                "parent rendered true",
+            ]
+        );
+        log.borrow_mut().clear();
+
+        let mut vnode = view(2);
+        vnode.apply(&dom, &parent, None, Some(ancestor));
+        assert_eq!(parent.text_content(), "22");
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "b rendered false",
+                "a rendered false",
+                "parent rendered false",
+            ]
+        );
+    }
+
+    #[derive(Clone, Properties)]
+    struct DropProps {
+        name: &'static str,
+        log: Log,
+        container: MemoryNode,
+    }
+
+    /// Logs how many nodes are still in its container when it's dropped.
+    struct Dropper {
+        props: DropProps,
+    }
+
+    impl Component for Dropper {
+        type Message = ();
+        type Properties = DropProps;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            Dropper { props }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn view(&self) -> Html {
+            if self.props.name != "parent" {
+                return html! { <span /> };
+            }
+            let DropProps { log, container, .. } = self.props.clone();
+            html! {
+                <div>
+                    <Dropper name="child" log=log container=container />
+                </div>
+            }
+        }
+    }
+
+    impl Drop for Dropper {
+        fn drop(&mut self) {
+            let attached = self.props.container.children().len();
+            let entry = format!("{} dropped, {} attached", self.props.name, attached);
+            self.props.log.borrow_mut().push(entry);
+        }
+    }
+
+    #[test]
+    fn drops_children_first_and_after_detaching() {
+        let dom = MemoryDom;
+        let container = dom.create_element("div", None);
+        let log = Log::default();
+        let mut vnode = html! {
+            <Dropper name="parent" log=log.clone() container=container.clone() />
+        };
+        vnode.apply(&dom, &container, None, None);
+        assert_eq!(container.children().len(), 1);
+
+        vnode.detach(&dom, &container);
+        assert!(container.children().is_empty());
+        assert_eq!(
+            *log.borrow(),
+            vec!["child dropped, 1 attached", "parent dropped, 0 attached"]
+        );
+    }
+}
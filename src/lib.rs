@@ -100,7 +100,10 @@ pub mod macros {
 pub mod app;
 pub mod callback;
 pub mod components;
+pub mod custom_element;
+mod executor;
 pub mod format;
+pub mod functional;
 pub mod html;
 mod scheduler;
 pub mod utils;
@@ -194,7 +197,7 @@ pub mod prelude {
         Renderable, ShouldRender,
     };
     pub use crate::macros::*;
-    pub use crate::virtual_dom::Classes;
+    pub use crate::virtual_dom::{Classes, Styles};
 
     /// Prelude module for creating worker.
     #[cfg(feature = "agent")]
@@ -28,6 +28,7 @@ pub(crate) struct Scheduler {
     main: Shared<VecDeque<Box<dyn Runnable>>>,
     create_component: Shared<VecDeque<Box<dyn Runnable>>>,
     mount_component: Shared<Vec<Box<dyn Runnable>>>,
+    rendered_component: Shared<Vec<Box<dyn Runnable>>>,
 }
 
 impl Scheduler {
@@ -37,6 +38,7 @@ impl Scheduler {
             main: Rc::new(RefCell::new(VecDeque::new())),
             create_component: Rc::new(RefCell::new(VecDeque::new())),
             mount_component: Rc::new(RefCell::new(Vec::new())),
+            rendered_component: Rc::new(RefCell::new(Vec::new())),
         }
     }
 
@@ -45,13 +47,20 @@ impl Scheduler {
         self.start();
     }
 
-    pub(crate) fn push_create(&self, runnable: Box<dyn Runnable>) {
-        self.create_component.borrow_mut().push_back(runnable);
+    /// Both tasks are queued before the scheduler starts, so a component is mounted before
+    /// its first render is reported, even if it's created right away.
+    pub(crate) fn push_create_and_mount(
+        &self,
+        create: Box<dyn Runnable>,
+        mount: Box<dyn Runnable>,
+    ) {
+        self.create_component.borrow_mut().push_back(create);
+        self.mount_component.borrow_mut().push(mount);
         self.start();
     }
 
-    pub(crate) fn push_mount(&self, runnable: Box<dyn Runnable>) {
-        self.mount_component.borrow_mut().push(runnable);
+    pub(crate) fn push_rendered(&self, runnable: Box<dyn Runnable>) {
+        self.rendered_component.borrow_mut().push(runnable);
         self.start();
     }
 
@@ -67,7 +76,9 @@ impl Scheduler {
                 .borrow_mut()
                 .pop_front()
                 .or_else(|| self.mount_component.borrow_mut().pop())
-                .or_else(|| self.main.borrow_mut().pop_front());
+                // Children are updated by tasks of the main queue after their parent rendered
+                .or_else(|| self.main.borrow_mut().pop_front())
+                .or_else(|| self.rendered_component.borrow_mut().pop());
             if let Some(runnable) = do_next {
                 runnable.run();
             } else {
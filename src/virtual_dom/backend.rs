@@ -0,0 +1,972 @@
+//! This module contains the DOM operations which the virtual DOM is built on, so the diffing
+//! logic doesn't depend on a particular DOM implementation.
+
+use super::{PropertyValue, Styles};
+use crate::utils::document;
+use cfg_if::cfg_if;
+use cfg_match::cfg_match;
+use indexmap::IndexMap;
+use log::warn;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        #[allow(unused_imports)]
+        use stdweb::{_js_impl, js};
+        use stdweb::unstable::TryFrom;
+        use stdweb::web::html_element::{InputElement, TextAreaElement};
+        use stdweb::web::{Element, IElement, INode, Node, TextNode};
+    } else if #[cfg(feature = "web_sys")] {
+        use std::ops::Deref;
+        use wasm_bindgen::JsCast;
+        use web_sys::{
+            CssStyleDeclaration, Element, HtmlInputElement as InputElement, HtmlOptionElement,
+            HtmlSelectElement, HtmlTextAreaElement as TextAreaElement, Node, Text as TextNode,
+        };
+    }
+}
+
+/// The DOM property which keeps the value of a controlled element.
+pub(crate) const CONTROLLED_VALUE: &str = "__yew_controlled_value";
+
+/// A node which the virtual DOM keeps between renders, tagged with the backend which
+/// created it. `T` is the type of the node in the browser DOM.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Handle<T> {
+    /// A node of the browser DOM.
+    Browser(T),
+    /// A node of a `MemoryDom`.
+    Memory(MemoryNode),
+}
+
+impl<T> Handle<T> {
+    /// Returns the node of the browser DOM, or `None` if it's kept by a `MemoryDom`.
+    pub fn browser(&self) -> Option<&T> {
+        match self {
+            Handle::Browser(node) => Some(node),
+            Handle::Memory(_) => None,
+        }
+    }
+
+    fn memory(&self) -> &MemoryNode {
+        match self {
+            Handle::Memory(node) => node,
+            Handle::Browser(_) => panic!("node isn't a part of a MemoryDom"),
+        }
+    }
+}
+
+/// Returns an element of the browser DOM as an element of `backend`, or `None` if the
+/// backend doesn't render into the browser DOM.
+///
+/// Nodes which are created outside of yew, like the host of a portal, are always a part of
+/// the browser DOM.
+pub(crate) fn browser_element<B: DomBackend>(backend: &B, element: &Element) -> Option<B::Element> {
+    if backend.is_browser() {
+        Some(
+            backend
+                .handle_element(&Handle::Browser(element.clone()))
+                .clone(),
+        )
+    } else {
+        None
+    }
+}
+
+/// Returns a node of the browser DOM as a node of `backend`, see `browser_element`.
+pub(crate) fn browser_node<B: DomBackend>(backend: &B, node: &Node) -> Option<B::Node> {
+    if backend.is_browser() {
+        Some(backend.handle_node(&Handle::Browser(node.clone())).clone())
+    } else {
+        None
+    }
+}
+
+/// Returns the node wrapped by `handle` as a node of `backend`, or `None` if it's a node of
+/// the browser DOM which `backend` can't hold, see `browser_element`.
+pub(crate) fn owned_node<B: DomBackend>(backend: &B, handle: &Handle<Node>) -> Option<B::Node> {
+    match handle {
+        Handle::Browser(node) => browser_node(backend, node),
+        Handle::Memory(_) => Some(backend.handle_node(handle).clone()),
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::Browser {}
+    impl Sealed for super::MemoryDom {}
+}
+
+/// The DOM operations used to render virtual nodes.
+///
+/// `Browser` implements them with the DOM of the enabled web backend, `MemoryDom` keeps
+/// the nodes in memory, so they can be used natively, e.g. in tests.
+///
+/// The trait is sealed: the virtual DOM keeps the nodes as a `Handle` between renders, which
+/// can only hold the nodes of these two backends.
+pub trait DomBackend: sealed::Sealed {
+    /// An element node.
+    type Element: Clone;
+    /// Any node of the tree.
+    type Node: Clone + PartialEq;
+    /// A text node.
+    type Text: Clone;
+
+    /// Creates an element, in the given namespace if there is one.
+    fn create_element(&self, tag: &str, namespace: Option<&str>) -> Self::Element;
+
+    /// Creates a text node.
+    fn create_text(&self, text: &str) -> Self::Text;
+
+    /// Parses markup into detached nodes.
+    fn parse_html(&self, html: &str) -> Vec<Self::Node>;
+
+    /// Returns true if the nodes are a part of the browser DOM, so the backend can hold
+    /// nodes which are created outside of yew.
+    fn is_browser(&self) -> bool;
+
+    /// Returns the namespace of an element.
+    fn namespace(&self, element: &Self::Element) -> Option<String>;
+
+    /// Converts an element to a node.
+    fn element_node(&self, element: &Self::Element) -> Self::Node;
+
+    /// Converts a text node to a node.
+    fn text_node(&self, text: &Self::Text) -> Self::Node;
+
+    /// Wraps an element, so the virtual DOM can keep it.
+    fn element_handle(&self, element: Self::Element) -> Handle<Element>;
+
+    /// Returns the element wrapped by `handle`. Panics if another backend created it.
+    fn handle_element<'a>(&self, handle: &'a Handle<Element>) -> &'a Self::Element;
+
+    /// Wraps a node, so the virtual DOM can keep it.
+    fn node_handle(&self, node: Self::Node) -> Handle<Node>;
+
+    /// Returns the node wrapped by `handle`. Panics if another backend created it.
+    fn handle_node<'a>(&self, handle: &'a Handle<Node>) -> &'a Self::Node;
+
+    /// Wraps a text node, so the virtual DOM can keep it.
+    fn text_handle(&self, text: Self::Text) -> Handle<TextNode>;
+
+    /// Returns the text node wrapped by `handle`. Panics if another backend created it.
+    fn handle_text<'a>(&self, handle: &'a Handle<TextNode>) -> &'a Self::Text;
+
+    /// Inserts `node` before `next_sibling`, or appends it to `parent` if there is none.
+    fn insert_before(
+        &self,
+        parent: &Self::Element,
+        node: &Self::Node,
+        next_sibling: Option<&Self::Node>,
+    );
+
+    /// Removes `node` from `parent`. Returns `false` if it's not a child of `parent`.
+    fn remove_child(&self, parent: &Self::Element, node: &Self::Node) -> bool;
+
+    /// Returns the node which follows `node` in its parent.
+    fn next_sibling(&self, node: &Self::Node) -> Option<Self::Node>;
+
+    /// Replaces the content of a text node.
+    fn set_text(&self, text: &Self::Text, value: &str);
+
+    /// Sets an attribute of an element.
+    fn set_attribute(&self, element: &Self::Element, name: &str, value: &str);
+
+    /// Removes an attribute of an element.
+    fn remove_attribute(&self, element: &Self::Element, name: &str);
+
+    /// Adds a class to the class list of an element.
+    fn add_class(&self, element: &Self::Element, class: &str);
+
+    /// Removes a class from the class list of an element.
+    fn remove_class(&self, element: &Self::Element, class: &str);
+
+    /// Sets an inline style property of an element.
+    fn set_style(&self, element: &Self::Element, property: &str, value: &str);
+
+    /// Removes an inline style property of an element.
+    fn remove_style(&self, element: &Self::Element, property: &str);
+
+    /// Sets a DOM property of an element.
+    fn set_property(&self, element: &Self::Element, name: &str, value: &PropertyValue);
+
+    /// Resets a DOM property of an element to `undefined`.
+    fn remove_property(&self, element: &Self::Element, name: &str);
+
+    /// Sets the `type` of an input element.
+    fn set_kind(&self, element: &Self::Element, kind: &str);
+
+    /// Returns the live value of an input or textarea element.
+    fn value(&self, element: &Self::Element) -> Option<String>;
+
+    /// Sets the value of an input or textarea element.
+    fn set_value(&self, element: &Self::Element, value: &str);
+
+    /// Returns the value which a controlled input or textarea must show.
+    fn controlled_value(&self, element: &Self::Element) -> Option<String>;
+
+    /// Keeps the value which a controlled input or textarea must show, or forgets it
+    /// if the element isn't controlled anymore.
+    fn set_controlled_value(&self, element: &Self::Element, value: Option<&str>);
+
+    /// Sets the `checked` state of an input element.
+    fn set_checked(&self, element: &Self::Element, checked: bool);
+
+    /// Selects the options of a select element which have one of `values` and deselects
+    /// the others.
+    fn set_selected(&self, element: &Self::Element, values: &[&str]);
+}
+
+/// The DOM of the browser, accessed through the enabled web backend.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Browser;
+
+impl DomBackend for Browser {
+    type Element = Element;
+    type Node = Node;
+    type Text = TextNode;
+
+    fn create_element(&self, tag: &str, namespace: Option<&str>) -> Element {
+        if let Some(namespace) = namespace {
+            #[cfg(feature = "web_sys")]
+            let namespace = Some(namespace);
+            document()
+                .create_element_ns(namespace, tag)
+                .expect("can't create namespaced element for vtag")
+        } else {
+            document()
+                .create_element(tag)
+                .expect("can't create element for vtag")
+        }
+    }
+
+    fn create_text(&self, text: &str) -> TextNode {
+        document().create_text_node(text)
+    }
+
+    fn parse_html(&self, html: &str) -> Vec<Node> {
+        let container = self.create_element("div", None);
+        cfg_match! {
+            feature = "std_web" => js! { @(no_return) @{&container}.innerHTML = @{html}; },
+            feature = "web_sys" => container.set_inner_html(html),
+        };
+        let mut nodes = Vec::new();
+        let mut next_child = container.first_child();
+        while let Some(child) = next_child {
+            next_child = child.next_sibling();
+            nodes.push(child);
+        }
+        nodes
+    }
+
+    fn is_browser(&self) -> bool {
+        true
+    }
+
+    fn namespace(&self, element: &Element) -> Option<String> {
+        element.namespace_uri()
+    }
+
+    fn element_node(&self, element: &Element) -> Node {
+        let node = cfg_match! {
+            feature = "std_web" => element.as_node(),
+            feature = "web_sys" => element.deref(),
+        };
+        node.to_owned()
+    }
+
+    fn text_node(&self, text: &TextNode) -> Node {
+        let node = cfg_match! {
+            feature = "std_web" => text.as_node(),
+            feature = "web_sys" => text.deref().deref(),
+        };
+        node.to_owned()
+    }
+
+    fn element_handle(&self, element: Element) -> Handle<Element> {
+        Handle::Browser(element)
+    }
+
+    fn handle_element<'a>(&self, handle: &'a Handle<Element>) -> &'a Element {
+        handle
+            .browser()
+            .expect("element isn't a part of the browser DOM")
+    }
+
+    fn node_handle(&self, node: Node) -> Handle<Node> {
+        Handle::Browser(node)
+    }
+
+    fn handle_node<'a>(&self, handle: &'a Handle<Node>) -> &'a Node {
+        handle
+            .browser()
+            .expect("node isn't a part of the browser DOM")
+    }
+
+    fn text_handle(&self, text: TextNode) -> Handle<TextNode> {
+        Handle::Browser(text)
+    }
+
+    fn handle_text<'a>(&self, handle: &'a Handle<TextNode>) -> &'a TextNode {
+        handle
+            .browser()
+            .expect("text node isn't a part of the browser DOM")
+    }
+
+    fn insert_before(&self, parent: &Element, node: &Node, next_sibling: Option<&Node>) {
+        if let Some(next_sibling) = next_sibling {
+            #[cfg(feature = "web_sys")]
+            let next_sibling = Some(next_sibling);
+            parent
+                .insert_before(node, next_sibling)
+                .expect("can't insert node before next sibling");
+        } else {
+            #[cfg_attr(feature = "std_web", allow(clippy::let_unit_value, unused_variables))]
+            {
+                let result = parent.append_child(node);
+                #[cfg(feature = "web_sys")]
+                result.expect("can't append node to parent");
+            }
+        }
+    }
+
+    fn remove_child(&self, parent: &Element, node: &Node) -> bool {
+        parent.remove_child(node).is_ok()
+    }
+
+    fn next_sibling(&self, node: &Node) -> Option<Node> {
+        node.next_sibling()
+    }
+
+    fn set_text(&self, text: &TextNode, value: &str) {
+        text.set_node_value(Some(value));
+    }
+
+    fn set_attribute(&self, element: &Element, name: &str, value: &str) {
+        element
+            .set_attribute(name, value)
+            .expect("invalid attribute key");
+    }
+
+    fn remove_attribute(&self, element: &Element, name: &str) {
+        cfg_match! {
+            feature = "std_web" => element.remove_attribute(name),
+            feature = "web_sys" => element.remove_attribute(name).expect("could not remove attribute"),
+        };
+    }
+
+    fn add_class(&self, element: &Element, class: &str) {
+        let result = cfg_match! {
+            feature = "std_web" => element.class_list().add(class),
+            feature = "web_sys" => element.class_list().add_1(class),
+        };
+        if let Err(error) = result {
+            warn!("Can't add class {:?}: {:?}", class, error);
+        }
+    }
+
+    fn remove_class(&self, element: &Element, class: &str) {
+        let result = cfg_match! {
+            feature = "std_web" => element.class_list().remove(class),
+            feature = "web_sys" => element.class_list().remove_1(class),
+        };
+        if let Err(error) = result {
+            warn!("Can't remove class {:?}: {:?}", class, error);
+        }
+    }
+
+    /// A trailing `!important` is passed as the priority, because `setProperty` doesn't
+    /// accept it as a part of the value.
+    fn set_style(&self, element: &Element, property: &str, value: &str) {
+        let important = "!important";
+        let (value, priority) = match value.len().checked_sub(important.len()) {
+            Some(end)
+                if value
+                    .get(end..)
+                    .filter(|s| s.eq_ignore_ascii_case(important))
+                    .is_some() =>
+            {
+                (value[..end].trim_end(), "important")
+            }
+            _ => (value, ""),
+        };
+        cfg_match! {
+            feature = "std_web" => js!( @(no_return) @{element}.style.setProperty(@{property}, @{value}, @{priority}); ),
+            feature = "web_sys" => inline_style(element)
+                .set_property_with_priority(property, value, priority)
+                .expect("could not set style"),
+        };
+    }
+
+    fn remove_style(&self, element: &Element, property: &str) {
+        #[cfg(feature = "std_web")]
+        js!( @(no_return) @{element}.style.removeProperty(@{property}); );
+        #[cfg(feature = "web_sys")]
+        inline_style(element)
+            .remove_property(property)
+            .expect("could not remove style");
+    }
+
+    fn set_property(&self, element: &Element, name: &str, value: &PropertyValue) {
+        cfg_match! {
+            feature = "std_web" => js!( @(no_return) @{element}[@{name}] = @{value}; ),
+            feature = "web_sys" => js_sys::Reflect::set(element, &PropertyValue::from_str(name), value)
+                .expect("could not set property"),
+        };
+    }
+
+    fn remove_property(&self, element: &Element, name: &str) {
+        let undefined = cfg_match! {
+            feature = "std_web" => PropertyValue::Undefined,
+            feature = "web_sys" => PropertyValue::UNDEFINED,
+        };
+        self.set_property(element, name, &undefined);
+    }
+
+    fn set_kind(&self, element: &Element, kind: &str) {
+        if let Some(input) = {
+            cfg_match! {
+                feature = "std_web" => InputElement::try_from(element.clone()).ok(),
+                feature = "web_sys" => element.dyn_ref::<InputElement>(),
+            }
+        } {
+            cfg_match! {
+                feature = "std_web" => ({
+                    //https://github.com/koute/stdweb/commit/3b85c941db00b8e3c942624afd50c5929085fb08
+                    //input.set_kind(&kind);
+                    let input = &input;
+                    js! { @(no_return)
+                        @{input}.type = @{kind};
+                    }
+                }),
+                feature = "web_sys" => input.set_type(kind),
+            }
+        }
+    }
+
+    fn value(&self, element: &Element) -> Option<String> {
+        if let Some(input) = {
+            cfg_match! {
+                feature = "std_web" => InputElement::try_from(element.clone()).ok(),
+                feature = "web_sys" => element.dyn_ref::<InputElement>(),
+            }
+        } {
+            Some(cfg_match! {
+                feature = "std_web" => input.raw_value(),
+                feature = "web_sys" => input.value(),
+            })
+        } else {
+            cfg_match! {
+                feature = "std_web" => TextAreaElement::try_from(element.clone()).ok(),
+                feature = "web_sys" => element.dyn_ref::<TextAreaElement>(),
+            }
+            .map(|tae| tae.value())
+        }
+    }
+
+    fn set_value(&self, element: &Element, value: &str) {
+        if let Some(input) = {
+            cfg_match! {
+                feature = "std_web" => InputElement::try_from(element.clone()).ok(),
+                feature = "web_sys" => element.dyn_ref::<InputElement>(),
+            }
+        } {
+            cfg_match! {
+                feature = "std_web" => input.set_raw_value(value),
+                feature = "web_sys" => input.set_value(value),
+            };
+        } else if let Some(tae) = {
+            cfg_match! {
+                feature = "std_web" => TextAreaElement::try_from(element.clone()).ok(),
+                feature = "web_sys" => element.dyn_ref::<TextAreaElement>(),
+            }
+        } {
+            tae.set_value(value);
+        }
+    }
+
+    fn controlled_value(&self, element: &Element) -> Option<String> {
+        cfg_match! {
+            feature = "std_web" => js!( return @{element}[@{CONTROLLED_VALUE}]; ).into_string(),
+            feature = "web_sys" => js_sys::Reflect::get(element, &PropertyValue::from_str(CONTROLLED_VALUE))
+                .ok()
+                .and_then(|value| value.as_string()),
+        }
+    }
+
+    fn set_controlled_value(&self, element: &Element, value: Option<&str>) {
+        match value {
+            Some(value) => {
+                self.set_property(element, CONTROLLED_VALUE, &PropertyValue::from(value))
+            }
+            None => self.remove_property(element, CONTROLLED_VALUE),
+        }
+    }
+
+    fn set_checked(&self, element: &Element, checked: bool) {
+        if let Some(input) = {
+            cfg_match! {
+                feature = "std_web" => InputElement::try_from(element.clone()).ok(),
+                feature = "web_sys" => element.dyn_ref::<InputElement>(),
+            }
+        } {
+            cfg_match! {
+                feature = "std_web" => js!( @(no_return) @{input}.checked = @{checked}; ),
+                feature = "web_sys" => input.set_checked(checked),
+            };
+        }
+    }
+
+    fn set_selected(&self, element: &Element, values: &[&str]) {
+        cfg_match! {
+            feature = "std_web" => js! { @(no_return)
+                var element = @{element};
+                var values = @{values.to_vec()};
+                if (element instanceof HTMLSelectElement) {
+                    for (var i = 0; i < element.options.length; i++) {
+                        var option = element.options[i];
+                        option.selected = values.indexOf(option.value) >= 0;
+                    }
+                }
+            },
+            feature = "web_sys" => if let Some(select) = element.dyn_ref::<HtmlSelectElement>() {
+                let options = (0..select.length())
+                    .filter_map(|index| select.item(index))
+                    .filter_map(|option| option.dyn_into::<HtmlOptionElement>().ok());
+                for option in options {
+                    option.set_selected(values.contains(&option.value().as_str()));
+                }
+            },
+        };
+    }
+}
+
+/// Returns the inline style declaration of HTML and SVG elements alike.
+#[cfg(feature = "web_sys")]
+fn inline_style(element: &Element) -> CssStyleDeclaration {
+    js_sys::Reflect::get(element, &PropertyValue::from_str("style"))
+        .expect("element has no style")
+        .unchecked_into()
+}
+
+/// A DOM which keeps its nodes in memory. It doesn't need a browser, so it can be used
+/// natively, e.g. to test how virtual nodes are patched.
+///
+/// Nodes which are created outside of yew, like the element of a `VExternal`, the node of a
+/// `VRef` and the host of a `VPortal`, are a part of the browser DOM, so they are left out
+/// together with the content of portals. Raw HTML is parsed without fixing up misnested
+/// tags like a browser does.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryDom;
+
+/// A node of a `MemoryDom`. Clones refer to the same node.
+#[derive(Clone)]
+pub struct MemoryNode(Rc<RefCell<MemoryNodeData>>);
+
+struct MemoryNodeData {
+    content: MemoryContent,
+    parent: Weak<RefCell<MemoryNodeData>>,
+    children: Vec<MemoryNode>,
+}
+
+enum MemoryContent {
+    Element(Box<MemoryElement>),
+    Text(String),
+}
+
+struct MemoryElement {
+    tag: String,
+    namespace: Option<String>,
+    attributes: IndexMap<String, String>,
+    properties: IndexMap<String, PropertyValue>,
+    value: String,
+    /// The value which a controlled input or textarea must show.
+    controlled_value: Option<String>,
+    checked: bool,
+}
+
+impl MemoryNode {
+    fn new(content: MemoryContent) -> Self {
+        MemoryNode(Rc::new(RefCell::new(MemoryNodeData {
+            content,
+            parent: Weak::new(),
+            children: Vec::new(),
+        })))
+    }
+
+    /// Returns the tag of an element, or `None` for a text node.
+    pub fn tag(&self) -> Option<String> {
+        match &self.0.borrow().content {
+            MemoryContent::Element(data) => Some(data.tag.clone()),
+            MemoryContent::Text(_) => None,
+        }
+    }
+
+    /// Returns an attribute of an element.
+    pub fn attribute(&self, name: &str) -> Option<String> {
+        match &self.0.borrow().content {
+            MemoryContent::Element(data) => data.attributes.get(name).cloned(),
+            MemoryContent::Text(_) => None,
+        }
+    }
+
+    /// Returns a DOM property of an element.
+    pub fn property(&self, name: &str) -> Option<PropertyValue> {
+        match &self.0.borrow().content {
+            MemoryContent::Element(data) => data.properties.get(name).cloned(),
+            MemoryContent::Text(_) => None,
+        }
+    }
+
+    /// Returns the value of an input or textarea element.
+    pub fn value(&self) -> String {
+        match &self.0.borrow().content {
+            MemoryContent::Element(data) => data.value.clone(),
+            MemoryContent::Text(_) => String::new(),
+        }
+    }
+
+    /// Returns the `checked` state of an input element or whether an option is selected.
+    pub fn checked(&self) -> bool {
+        match &self.0.borrow().content {
+            MemoryContent::Element(data) => data.checked,
+            MemoryContent::Text(_) => false,
+        }
+    }
+
+    /// Returns the children of the node.
+    pub fn children(&self) -> Vec<MemoryNode> {
+        self.0.borrow().children.clone()
+    }
+
+    /// Returns the text of the node and its descendants.
+    pub fn text_content(&self) -> String {
+        match &self.0.borrow().content {
+            MemoryContent::Text(text) => text.clone(),
+            MemoryContent::Element(_) => self
+                .children()
+                .iter()
+                .map(MemoryNode::text_content)
+                .collect(),
+        }
+    }
+
+    fn update_attribute(&self, name: &str, update: impl FnOnce(Option<&String>) -> Option<String>) {
+        if let MemoryContent::Element(data) = &mut self.0.borrow_mut().content {
+            match update(data.attributes.get(name)) {
+                Some(value) => {
+                    data.attributes.insert(name.to_owned(), value);
+                }
+                None => {
+                    data.attributes.shift_remove(name);
+                }
+            }
+        }
+    }
+
+    fn position_in(&self, parent: &MemoryNode) -> Option<usize> {
+        parent
+            .0
+            .borrow()
+            .children
+            .iter()
+            .position(|child| child == self)
+    }
+}
+
+impl PartialEq for MemoryNode {
+    fn eq(&self, other: &MemoryNode) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for MemoryNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0.borrow().content {
+            MemoryContent::Element(data) => write!(f, "MemoryNode {{ tag: {} }}", data.tag),
+            MemoryContent::Text(text) => write!(f, "MemoryNode {{ text: {} }}", text),
+        }
+    }
+}
+
+impl DomBackend for MemoryDom {
+    type Element = MemoryNode;
+    type Node = MemoryNode;
+    type Text = MemoryNode;
+
+    fn create_element(&self, tag: &str, namespace: Option<&str>) -> MemoryNode {
+        MemoryNode::new(MemoryContent::Element(Box::new(MemoryElement {
+            tag: tag.to_owned(),
+            namespace: namespace.map(str::to_owned),
+            attributes: IndexMap::new(),
+            properties: IndexMap::new(),
+            value: String::new(),
+            controlled_value: None,
+            checked: false,
+        })))
+    }
+
+    fn create_text(&self, text: &str) -> MemoryNode {
+        MemoryNode::new(MemoryContent::Text(text.to_owned()))
+    }
+
+    fn parse_html(&self, html: &str) -> Vec<MemoryNode> {
+        super::vraw::parse_markup(self, html)
+    }
+
+    fn is_browser(&self) -> bool {
+        false
+    }
+
+    fn namespace(&self, element: &MemoryNode) -> Option<String> {
+        match &element.0.borrow().content {
+            MemoryContent::Element(data) => data.namespace.clone(),
+            MemoryContent::Text(_) => None,
+        }
+    }
+
+    fn element_node(&self, element: &MemoryNode) -> MemoryNode {
+        element.clone()
+    }
+
+    fn text_node(&self, text: &MemoryNode) -> MemoryNode {
+        text.clone()
+    }
+
+    fn element_handle(&self, element: MemoryNode) -> Handle<Element> {
+        Handle::Memory(element)
+    }
+
+    fn handle_element<'a>(&self, handle: &'a Handle<Element>) -> &'a MemoryNode {
+        handle.memory()
+    }
+
+    fn node_handle(&self, node: MemoryNode) -> Handle<Node> {
+        Handle::Memory(node)
+    }
+
+    fn handle_node<'a>(&self, handle: &'a Handle<Node>) -> &'a MemoryNode {
+        handle.memory()
+    }
+
+    fn text_handle(&self, text: MemoryNode) -> Handle<TextNode> {
+        Handle::Memory(text)
+    }
+
+    fn handle_text<'a>(&self, handle: &'a Handle<TextNode>) -> &'a MemoryNode {
+        handle.memory()
+    }
+
+    fn insert_before(
+        &self,
+        parent: &MemoryNode,
+        node: &MemoryNode,
+        next_sibling: Option<&MemoryNode>,
+    ) {
+        let old_parent = node.0.borrow().parent.upgrade().map(MemoryNode);
+        if let Some(old_parent) = old_parent {
+            self.remove_child(&old_parent, node);
+        }
+        let index = next_sibling
+            .and_then(|next_sibling| next_sibling.position_in(parent))
+            .unwrap_or_else(|| parent.0.borrow().children.len());
+        parent.0.borrow_mut().children.insert(index, node.clone());
+        node.0.borrow_mut().parent = Rc::downgrade(&parent.0);
+    }
+
+    fn remove_child(&self, parent: &MemoryNode, node: &MemoryNode) -> bool {
+        match node.position_in(parent) {
+            Some(index) => {
+                parent.0.borrow_mut().children.remove(index);
+                node.0.borrow_mut().parent = Weak::new();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn next_sibling(&self, node: &MemoryNode) -> Option<MemoryNode> {
+        let parent = MemoryNode(node.0.borrow().parent.upgrade()?);
+        let index = node.position_in(&parent)?;
+        let sibling = parent.0.borrow().children.get(index + 1).cloned();
+        sibling
+    }
+
+    fn set_text(&self, text: &MemoryNode, value: &str) {
+        if let MemoryContent::Text(text) = &mut text.0.borrow_mut().content {
+            *text = value.to_owned();
+        }
+    }
+
+    fn set_attribute(&self, element: &MemoryNode, name: &str, value: &str) {
+        element.update_attribute(name, |_| Some(value.to_owned()));
+    }
+
+    fn remove_attribute(&self, element: &MemoryNode, name: &str) {
+        element.update_attribute(name, |_| None);
+    }
+
+    fn add_class(&self, element: &MemoryNode, class: &str) {
+        element.update_attribute("class", |classes| {
+            let mut classes: Vec<&str> = classes
+                .map(|classes| classes.split_whitespace().collect())
+                .unwrap_or_default();
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+            Some(classes.join(" "))
+        });
+    }
+
+    fn remove_class(&self, element: &MemoryNode, class: &str) {
+        element.update_attribute("class", |classes| {
+            let classes = classes?
+                .split_whitespace()
+                .filter(|other| *other != class)
+                .collect::<Vec<&str>>();
+            Some(classes.join(" "))
+        });
+    }
+
+    fn set_style(&self, element: &MemoryNode, property: &str, value: &str) {
+        element.update_attribute("style", |styles| {
+            let mut styles = styles.map(Styles::from).unwrap_or_default();
+            styles.push(property, value);
+            Some(styles.to_string())
+        });
+    }
+
+    fn remove_style(&self, element: &MemoryNode, property: &str) {
+        element.update_attribute("style", |styles| {
+            let styles = Styles::from(styles?);
+            let styles: Vec<(&str, &str)> = styles
+                .iter()
+                .filter(|(other, _)| *other != property)
+                .collect();
+            Some(Styles::from(styles).to_string())
+        });
+    }
+
+    fn set_property(&self, element: &MemoryNode, name: &str, value: &PropertyValue) {
+        if let MemoryContent::Element(data) = &mut element.0.borrow_mut().content {
+            data.properties.insert(name.to_owned(), value.clone());
+        }
+    }
+
+    fn remove_property(&self, element: &MemoryNode, name: &str) {
+        if let MemoryContent::Element(data) = &mut element.0.borrow_mut().content {
+            data.properties.shift_remove(name);
+        }
+    }
+
+    fn set_kind(&self, element: &MemoryNode, kind: &str) {
+        self.set_attribute(element, "type", kind);
+    }
+
+    fn value(&self, element: &MemoryNode) -> Option<String> {
+        match &element.0.borrow().content {
+            MemoryContent::Element(data) => Some(data.value.clone()),
+            MemoryContent::Text(_) => None,
+        }
+    }
+
+    fn set_value(&self, element: &MemoryNode, new_value: &str) {
+        if let MemoryContent::Element(data) = &mut element.0.borrow_mut().content {
+            data.value = new_value.to_owned();
+        }
+    }
+
+    fn controlled_value(&self, element: &MemoryNode) -> Option<String> {
+        match &element.0.borrow().content {
+            MemoryContent::Element(data) => data.controlled_value.clone(),
+            MemoryContent::Text(_) => None,
+        }
+    }
+
+    fn set_controlled_value(&self, element: &MemoryNode, value: Option<&str>) {
+        if let MemoryContent::Element(data) = &mut element.0.borrow_mut().content {
+            data.controlled_value = value.map(str::to_owned);
+        }
+    }
+
+    fn set_checked(&self, element: &MemoryNode, new_checked: bool) {
+        if let MemoryContent::Element(data) = &mut element.0.borrow_mut().content {
+            data.checked = new_checked;
+        }
+    }
+
+    fn set_selected(&self, element: &MemoryNode, values: &[&str]) {
+        for child in element.children() {
+            if child.tag().as_deref() == Some("option") {
+                let value = child
+                    .attribute("value")
+                    .unwrap_or_else(|| child.text_content());
+                self.set_checked(&child, values.contains(&value.as_str()));
+            } else {
+                // Options may be grouped
+                self.set_selected(&child, values);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[test]
+    fn memory_dom_keeps_order_of_children() {
+        let dom = MemoryDom;
+        let parent = dom.create_element("ul", None);
+        let first = dom.create_element("li", None);
+        let second = dom.create_text("second");
+
+        dom.insert_before(&parent, &second, None);
+        dom.insert_before(&parent, &first, Some(&second));
+        assert_eq!(parent.children(), vec![first.clone(), second.clone()]);
+        assert_eq!(dom.next_sibling(&first), Some(second.clone()));
+
+        // Inserting a node again moves it
+        dom.insert_before(&parent, &second, Some(&first));
+        assert_eq!(parent.children(), vec![second.clone(), first.clone()]);
+        assert_eq!(dom.next_sibling(&first), None);
+
+        assert!(dom.remove_child(&parent, &second));
+        assert!(!dom.remove_child(&parent, &second));
+        assert_eq!(parent.children(), vec![first]);
+    }
+
+    #[test]
+    fn memory_dom_keeps_properties() {
+        let dom = MemoryDom;
+        let element = dom.create_element("input", None);
+        let value = PropertyValue::from(true);
+
+        dom.set_property(&element, "indeterminate", &value);
+        assert_eq!(element.property("indeterminate"), Some(value));
+        dom.remove_property(&element, "indeterminate");
+        assert_eq!(element.property("indeterminate"), None);
+    }
+
+    #[test]
+    fn browser_sets_important_styles() {
+        let element = Browser.create_element("div", None);
+        Browser.set_style(&element, "color", "red !important");
+        // The priority is split off at a byte offset which isn't a char boundary here
+        Browser.set_style(&element, "font-family", "€€€€");
+        let priority = cfg_match! {
+            feature = "std_web" => js!(return @{&element}.style.getPropertyPriority("color");)
+                .into_string()
+                .unwrap(),
+            feature = "web_sys" => inline_style(&element).get_property_priority("color"),
+        };
+        assert_eq!(priority, "important");
+    }
+}
@@ -1,49 +1,129 @@
 //! This module contains the implementation of reactive virtual dom concept.
 
+pub mod backend;
 #[doc(hidden)]
 pub mod vcomp;
 #[doc(hidden)]
+pub mod vexternal;
+#[doc(hidden)]
 pub mod vlist;
 #[doc(hidden)]
+pub mod vmemo;
+#[doc(hidden)]
 pub mod vnode;
 #[doc(hidden)]
+pub mod vportal;
+#[doc(hidden)]
+pub mod vraw;
+#[doc(hidden)]
+pub mod vsuspense;
+#[doc(hidden)]
 pub mod vtag;
 #[doc(hidden)]
 pub mod vtext;
 
 use cfg_if::cfg_if;
-use indexmap::set::IndexSet;
+use indexmap::{map::IndexMap, set::IndexSet};
+use std::any::Any;
 use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
 cfg_if! {
     if #[cfg(feature = "std_web")] {
         use crate::html::EventListener;
-        use stdweb::web::{Element, Node};
+        use stdweb::web::Element;
+
+        /// A value of a DOM property.
+        pub type PropertyValue = stdweb::Value;
     } else if #[cfg(feature = "web_sys")] {
         use gloo::events::EventListener;
-        use web_sys::{Element, Node};
+        use web_sys::Element;
+
+        /// A value of a DOM property.
+        pub type PropertyValue = wasm_bindgen::JsValue;
     }
 }
 
+#[doc(inline)]
+pub use self::backend::{Browser, DomBackend, Handle, MemoryDom, MemoryNode};
 #[doc(inline)]
 pub use self::vcomp::{VChild, VComp};
 #[doc(inline)]
+pub use self::vexternal::VExternal;
+#[doc(inline)]
 pub use self::vlist::VList;
 #[doc(inline)]
+pub use self::vmemo::VMemo;
+#[doc(inline)]
 pub use self::vnode::VNode;
 #[doc(inline)]
+pub use self::vportal::VPortal;
+#[doc(inline)]
+pub use self::vraw::{Allowlist, VRaw};
+#[doc(inline)]
+pub use self::vsuspense::VSuspense;
+#[doc(inline)]
 pub use self::vtag::VTag;
 #[doc(inline)]
 pub use self::vtext::VText;
 
 /// `Listener` trait is an universal implementation of an event listener
 /// which helps to bind Rust-listener to JS-listener (DOM).
-pub trait Listener {
+pub trait Listener: AsAny {
     /// Returns standard name of DOM's event.
     fn kind(&self) -> &'static str;
     /// Attaches a listener to the element.
     fn attach(&self, element: &Element) -> EventListener;
+    /// Makes the listener call the callback of `other` from now on if `other` handles the
+    /// same kind of event with the same options, and returns true. The attached listener is
+    /// kept in that case instead of attaching `other`. By default listeners are always
+    /// attached again.
+    fn take_callback(&self, _other: &dyn Listener) -> bool {
+        false
+    }
+    /// Returns the options the listener was created with, or `None` if it uses the
+    /// defaults for its kind of event. Only listeners with default options are delegated.
+    fn options(&self) -> Option<ListenerOptions> {
+        None
+    }
+    /// Returns the name of the DOM event which the root of an app can delegate to the
+    /// listener. By default listeners are attached to their element directly.
+    #[cfg(feature = "web_sys")]
+    fn delegated_event(&self) -> Option<&'static str> {
+        None
+    }
+    /// Calls the listener with an event which the root of an app delegated to `element`.
+    /// Only called if `delegated_event` returns the name of the event.
+    #[cfg(feature = "web_sys")]
+    fn handle(&self, _element: &Element, _event: &web_sys::Event) {}
+}
+
+/// Gives access to the concrete type of a trait object, e.g. to compare listeners.
+pub trait AsAny {
+    /// Returns the value as `Any`.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Options of an event listener, see
+/// [addEventListener](https://developer.mozilla.org/en-US/docs/Web/API/EventTarget/addEventListener#Parameters).
+///
+/// In `html!` they are added to the name of the listener, e.g. `onscroll-passive=...` or
+/// `onfocus-capture-once=...`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListenerOptions {
+    /// The listener never prevents the default action, so the browser doesn't have to
+    /// wait for it, e.g. to scroll.
+    pub passive: bool,
+    /// The listener is called in the capture phase, before the listeners of the target.
+    pub capture: bool,
+    /// The listener is removed after the first event.
+    pub once: bool,
 }
 
 impl fmt::Debug for dyn Listener {
@@ -58,6 +138,9 @@ type Listeners = Vec<Rc<dyn Listener>>;
 /// A map of attributes.
 type Attributes = HashMap<String, String>;
 
+/// A map of DOM properties.
+type Properties = HashMap<String, PropertyValue>;
+
 /// A set of classes.
 #[derive(Debug, Clone, Default)]
 pub struct Classes {
@@ -72,14 +155,16 @@ impl Classes {
         }
     }
 
-    /// Adds a class to a set.
+    /// Adds a class to a set. A string of classes separated by whitespace adds each of them.
     ///
     /// Prevents duplication of class names.
     pub fn push(&mut self, class: &str) {
-        let class = class.trim();
-        if !class.is_empty() {
-            self.set.insert(class.into());
-        }
+        self.set.extend(class.split_whitespace().map(String::from));
+    }
+
+    /// Returns an iterator over the classes.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.set.iter().map(String::as_str)
     }
 
     /// Check the set contains a class.
@@ -148,8 +233,8 @@ impl<T: AsRef<str>> From<Vec<T>> for Classes {
     fn from(t: Vec<T>) -> Self {
         let set = t
             .iter()
-            .map(|x| x.as_ref().to_string())
-            .filter(|c| !c.is_empty())
+            .flat_map(|x| x.as_ref().split_whitespace())
+            .map(String::from)
             .collect();
         Self { set }
     }
@@ -161,6 +246,136 @@ impl PartialEq for Classes {
     }
 }
 
+/// A map of inline styles which keeps the order of the properties.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Styles {
+    map: IndexMap<String, String>,
+}
+
+impl Styles {
+    /// Creates empty set of styles.
+    pub fn new() -> Self {
+        Self {
+            map: IndexMap::new(),
+        }
+    }
+
+    /// Sets a property, replacing its previous value.
+    ///
+    /// Properties with an empty name or value are ignored.
+    pub fn push(&mut self, property: &str, value: &str) {
+        let property = property.trim();
+        let value = value.trim();
+        if !property.is_empty() && !value.is_empty() {
+            self.map.insert(property.into(), value.into());
+        }
+    }
+
+    /// Returns the value of a property.
+    pub fn get(&self, property: &str) -> Option<&str> {
+        self.map.get(property).map(String::as_str)
+    }
+
+    /// Returns an iterator over the properties and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.map
+            .iter()
+            .map(|(property, value)| (property.as_str(), value.as_str()))
+    }
+
+    /// Check the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Adds other styles to these styles; returning itself.
+    ///
+    /// Properties of `other` take precedence.
+    pub fn extend<T: Into<Styles>>(mut self, other: T) -> Self {
+        self.map.extend(other.into().map);
+        self
+    }
+
+    /// Parses declarations like `color: red; margin: 0`. Semicolons inside of parentheses or
+    /// quotes, e.g. in `url(data:image/png;base64,...)`, don't end a declaration.
+    fn parse(declarations: &str) -> Self {
+        let mut styles = Styles::new();
+        let mut push = |declaration: &str| {
+            let mut parts = declaration.splitn(2, ':');
+            if let (Some(property), Some(value)) = (parts.next(), parts.next()) {
+                styles.push(property, value);
+            }
+        };
+
+        let mut start = 0;
+        let mut depth = 0;
+        let mut quote = None;
+        for (i, c) in declarations.char_indices() {
+            match (c, quote) {
+                (_, Some(q)) if c == q => quote = None,
+                (_, Some(_)) => {}
+                ('"', None) | ('\'', None) => quote = Some(c),
+                ('(', None) => depth += 1,
+                (')', None) if depth > 0 => depth -= 1,
+                (';', None) if depth == 0 => {
+                    push(&declarations[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        push(&declarations[start..]);
+        styles
+    }
+}
+
+impl fmt::Display for Styles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let declarations: Vec<String> = self
+            .map
+            .iter()
+            .map(|(property, value)| format!("{}: {};", property, value))
+            .collect();
+        f.write_str(&declarations.join(" "))
+    }
+}
+
+impl From<&str> for Styles {
+    fn from(t: &str) -> Self {
+        Styles::parse(t)
+    }
+}
+
+impl From<String> for Styles {
+    fn from(t: String) -> Self {
+        Styles::parse(&t)
+    }
+}
+
+impl From<&String> for Styles {
+    fn from(t: &String) -> Self {
+        Styles::parse(t)
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> From<(K, V)> for Styles {
+    fn from((property, value): (K, V)) -> Self {
+        let mut styles = Styles::new();
+        styles.push(property.as_ref(), value.as_ref());
+        styles
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> From<Vec<(K, V)>> for Styles {
+    fn from(t: Vec<(K, V)>) -> Self {
+        let mut styles = Styles::new();
+        for (property, value) in t {
+            styles.push(property.as_ref(), value.as_ref());
+        }
+        styles
+    }
+}
+
 /// Patch for DOM node modification.
 enum Patch<ID, T> {
     Add(ID, T),
@@ -169,7 +384,7 @@ enum Patch<ID, T> {
 }
 
 /// Reform of a node.
-enum Reform {
+enum Reform<N> {
     /// Don't create a NEW reference (js Node).
     ///
     /// The reference _may still be mutated_.
@@ -182,17 +397,15 @@ enum Reform {
     ///
     /// If it does not exist, a `previous_sibling` must be
     /// specified (see `VDiff::apply()`).
-    Before(Option<Node>),
+    Before(Option<N>),
 }
 
-// TODO(#938): What about to implement `VDiff` for `Element`?
-// In makes possible to include ANY element into the tree.
-// `Ace` editor embedding for example?
-
 /// This trait provides features to update a tree by calculating a difference against another tree.
+///
+/// The DOM is changed through the `backend`, which created the nodes kept by the tree.
 pub(crate) trait VDiff {
     /// Remove itself from parent and return the next sibling.
-    fn detach(&mut self, parent: &Element) -> Option<Node>;
+    fn detach<B: DomBackend>(&mut self, backend: &B, parent: &B::Element) -> Option<B::Node>;
 
     /// Scoped diff apply to other tree.
     ///
@@ -200,6 +413,7 @@ pub(crate) trait VDiff {
     /// to check the difference and apply patches to the actual DOM representation.
     ///
     /// Parameters:
+    /// - `backend`: the DOM which the node is rendered to.
     /// - `parent`: the parent node in the DOM.
     /// - `previous_sibling`: the "previous node" in a list of nodes, used to efficiently
     ///   find where to put the node.
@@ -215,12 +429,46 @@ pub(crate) trait VDiff {
     ///
     /// The exception to this is obviously `VRef` which simply uses the inner `Node` directly
     /// (always removes the `Node` that exists).
-    fn apply(
+    fn apply<B: DomBackend>(
         &mut self,
-        parent: &Element,
-        previous_sibling: Option<&Node>,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
         ancestor: Option<VNode>,
-    ) -> Option<Node>;
+    ) -> Option<B::Node>;
+}
+
+/// This trait provides features to render a tree into HTML markup without a DOM.
+pub(crate) trait VRender {
+    /// Appends the markup of the node to `html`.
+    ///
+    /// Nested components are created and rendered synchronously, they are never mounted.
+    fn render_html(&self, html: &mut String);
+}
+
+/// Escapes text so it can be used as the content of an HTML element.
+fn escape_html(text: &str, html: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            c => html.push(c),
+        }
+    }
+}
+
+/// Escapes text so it can be used as a double quoted HTML attribute value.
+fn escape_attribute(value: &str, html: &mut String) {
+    for c in value.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '"' => html.push_str("&quot;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            c => html.push(c),
+        }
+    }
 }
 
 /// Transform properties to the expected type.
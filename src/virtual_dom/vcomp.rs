@@ -1,27 +1,43 @@
 //! This module contains the implementation of a virtual component `VComp`.
 
-use super::{Transformer, VDiff, VNode};
-use crate::html::{Component, ComponentUpdate, HiddenScope, NodeRef, Scope};
-use crate::utils::document;
+use super::{DomBackend, Handle, Transformer, VDiff, VNode, VRender, VText};
+use crate::html::{Component, HiddenScope, NodeRef, Scope};
 use cfg_if::cfg_if;
+use log::warn;
 use std::any::TypeId;
-use std::fmt;
+use std::fmt::{self, Write};
 use std::mem::swap;
 use std::rc::Rc;
 cfg_if! {
     if #[cfg(feature = "std_web")] {
-        use stdweb::web::{Element, INode, Node, TextNode};
+        use stdweb::web::{Element, Node};
     } else if #[cfg(feature = "web_sys")] {
-        use web_sys::{Element, Node, Text as TextNode};
+        use web_sys::{Element, Node};
     }
 }
 
 /// The method generates an instance of a component.
 type Generator = dyn Fn(GeneratorType) -> Mounted;
 
+/// The method creates an instance of a component and renders it to HTML without mounting.
+type Renderer = dyn Fn(&mut String);
+
+/// The text of the comment which opens the markup of a component rendered to HTML.
+pub(crate) const COMPONENT_START: &str = "[";
+
+/// The text of the comment which closes the markup of a component rendered to HTML.
+pub(crate) const COMPONENT_END: &str = "]";
+
+/// The method returns the top level DOM nodes of a mounted component.
+type DomNodes = dyn Fn() -> Option<Vec<Handle<Node>>>;
+
+/// The method moves the DOM nodes of a mounted component to another parent.
+type Shifter = dyn Fn(Handle<Element>, Option<Handle<Node>>);
+
 /// Components can be generated by mounting or by overwriting an old component.
 enum GeneratorType {
-    Mount(Element, TextNode),
+    /// Mounts over the ancestor node, which marks the place of the component in the DOM.
+    Mount(Handle<Element>, VNode),
     Overwrite(HiddenScope),
 }
 
@@ -31,12 +47,16 @@ pub struct VComp {
     type_id: TypeId,
     state: MountState,
     pub(crate) node_ref: NodeRef,
+    /// A key used to match this component against its ancestor in a list of children.
+    pub key: Option<String>,
 }
 
 /// A virtual child component.
 pub struct VChild<COMP: Component> {
     /// The component properties
     pub props: COMP::Properties,
+    /// A key used to match this component against its ancestor in a list of children.
+    pub key: Option<String>,
     /// Reference to the mounted node
     node_ref: NodeRef,
 }
@@ -45,6 +65,7 @@ impl<COMP: Component> Clone for VChild<COMP> {
     fn clone(&self) -> Self {
         VChild {
             props: self.props.clone(),
+            key: self.key.clone(),
             node_ref: self.node_ref.clone(),
         }
     }
@@ -56,7 +77,11 @@ where
 {
     /// Creates a child component that can be accessed and modified by its parent.
     pub fn new(props: COMP::Properties, node_ref: NodeRef) -> Self {
-        Self { props, node_ref }
+        Self {
+            props,
+            key: None,
+            node_ref,
+        }
     }
 }
 
@@ -65,7 +90,9 @@ where
     COMP: Component,
 {
     fn from(vchild: VChild<COMP>) -> Self {
-        VComp::new::<COMP>(vchild.props, vchild.node_ref)
+        let mut vcomp = VComp::new::<COMP>(vchild.props, vchild.node_ref);
+        vcomp.key = vchild.key;
+        vcomp
     }
 }
 
@@ -81,12 +108,17 @@ enum MountState {
 #[derive(Clone)]
 struct Unmounted {
     generator: Rc<Generator>,
+    renderer: Rc<Renderer>,
 }
 
 struct Mounted {
     node_ref: NodeRef,
+    /// Stakes out the place of the component in the DOM until its first render.
+    placeholder: Option<Handle<Node>>,
     scope: HiddenScope,
     destroyer: Box<dyn FnOnce()>,
+    dom_nodes: Box<DomNodes>,
+    shifter: Box<Shifter>,
 }
 
 impl Clone for Mounted {
@@ -102,32 +134,53 @@ impl VComp {
         COMP: Component,
     {
         let node_ref_clone = node_ref.clone();
+        // The renderer and the generator share the properties, which are cloned when used
+        let props = Rc::new(props);
+        let renderer_props = props.clone();
+        let renderer = move |html: &mut String| {
+            Scope::<COMP>::new().render_html((*renderer_props).clone(), html)
+        };
         let generator = move |generator_type: GeneratorType| -> Mounted {
             match generator_type {
-                GeneratorType::Mount(element, dummy_node) => {
+                GeneratorType::Mount(element, ancestor) => {
                     let scope: Scope<COMP> = Scope::new();
 
+                    let placeholder = ancestor.dom_nodes().pop();
                     let mut scope = scope.mount_in_place(
                         element,
-                        Some(VNode::VRef(dummy_node.into())),
+                        Some(ancestor),
                         node_ref_clone.clone(),
-                        props.clone(),
+                        (*props).clone(),
                     );
 
+                    let rendered = scope.clone();
+                    let shifted = scope.clone();
                     Mounted {
                         node_ref: node_ref_clone.clone(),
+                        placeholder,
                         scope: scope.clone().into(),
                         destroyer: Box::new(move || scope.destroy()),
+                        dom_nodes: Box::new(move || rendered.dom_nodes()),
+                        shifter: Box::new(move |parent, next_sibling| {
+                            shifted.shift(parent, next_sibling)
+                        }),
                     }
                 }
                 GeneratorType::Overwrite(hidden_scope) => {
                     let mut scope: Scope<COMP> = hidden_scope.into();
-                    scope.update(ComponentUpdate::Properties(props.clone()));
+                    scope.reuse((*props).clone(), node_ref_clone.clone());
 
+                    let rendered = scope.clone();
+                    let shifted = scope.clone();
                     Mounted {
                         node_ref: node_ref_clone.clone(),
+                        placeholder: None,
                         scope: scope.clone().into(),
                         destroyer: Box::new(move || scope.destroy()),
+                        dom_nodes: Box::new(move || rendered.dom_nodes()),
+                        shifter: Box::new(move |parent, next_sibling| {
+                            shifted.shift(parent, next_sibling)
+                        }),
                     }
                 }
             }
@@ -137,16 +190,58 @@ impl VComp {
             type_id: TypeId::of::<COMP>(),
             state: MountState::Unmounted(Unmounted {
                 generator: Rc::new(generator),
+                renderer: Rc::new(renderer),
             }),
             node_ref,
+            key: None,
+        }
+    }
+
+    /// Returns the top level DOM nodes of the mounted component in document order, or the
+    /// placeholder node which stands in for it until the component is rendered.
+    pub(crate) fn dom_nodes(&self) -> Vec<Handle<Node>> {
+        match &self.state {
+            MountState::Mounted(mounted) => {
+                (mounted.dom_nodes)().unwrap_or_else(|| self.dom_node().into_iter().collect())
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the root DOM node of the mounted component, or the placeholder node which
+    /// stands in for it until the component is rendered.
+    pub(crate) fn dom_node(&self) -> Option<Handle<Node>> {
+        match &self.state {
+            MountState::Mounted(mounted) => mounted
+                .node_ref
+                .handle()
+                .or_else(|| mounted.placeholder.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl VComp {
+    /// Moves the DOM nodes of the mounted component to `next_parent`, see `VNode::shift`.
+    pub(crate) fn shift<B: DomBackend>(
+        &self,
+        backend: &B,
+        next_parent: &B::Element,
+        next_sibling: Option<&B::Node>,
+    ) {
+        if let MountState::Mounted(mounted) = &self.state {
+            (mounted.shifter)(
+                backend.element_handle(next_parent.clone()),
+                next_sibling.map(|node| backend.node_handle(node.clone())),
+            );
         }
     }
 }
 
 impl Unmounted {
     /// Mount a virtual component using a generator.
-    fn mount(self, parent: Element, dummy_node: TextNode) -> Mounted {
-        (self.generator)(GeneratorType::Mount(parent, dummy_node))
+    fn mount(self, parent: Handle<Element>, ancestor: VNode) -> Mounted {
+        (self.generator)(GeneratorType::Mount(parent, ancestor))
     }
 
     /// Overwrite an existing virtual component using a generator.
@@ -155,30 +250,37 @@ impl Unmounted {
     }
 }
 
-enum Reform {
+enum Reform<N> {
     Keep(Mounted),
-    Before(Option<Node>),
+    Adopt(VNode),
+    Before(Option<N>),
 }
 
 impl VDiff for VComp {
-    fn detach(&mut self, _parent: &Element) -> Option<Node> {
+    fn detach<B: DomBackend>(&mut self, backend: &B, _parent: &B::Element) -> Option<B::Node> {
         let mut replace_state = MountState::Detached;
         swap(&mut replace_state, &mut self.state);
         match replace_state {
             MountState::Mounted(this) => {
+                // The component removes its nodes when it's destroyed
+                let next_sibling = this
+                    .node_ref
+                    .handle()
+                    .and_then(|node| backend.next_sibling(backend.handle_node(&node)));
                 (this.destroyer)();
-                this.node_ref.get().and_then(|node| node.next_sibling())
+                next_sibling
             }
             _ => None,
         }
     }
 
-    fn apply(
+    fn apply<B: DomBackend>(
         &mut self,
-        parent: &Element,
-        previous_sibling: Option<&Node>,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
         ancestor: Option<VNode>,
-    ) -> Option<Node> {
+    ) -> Option<B::Node> {
         let mut replace_state = MountState::Mounting;
         swap(&mut replace_state, &mut self.state);
         if let MountState::Unmounted(this) = replace_state {
@@ -186,7 +288,7 @@ impl VDiff for VComp {
                 Some(VNode::VComp(mut vcomp)) => {
                     // If the ancestor is a Component of the same type, don't replace, keep the
                     // old Component but update the properties.
-                    if self.type_id == vcomp.type_id {
+                    if self.type_id == vcomp.type_id && self.key == vcomp.key {
                         let mut replace_state = MountState::Overwritten;
                         swap(&mut replace_state, &mut vcomp.state);
                         match replace_state {
@@ -194,10 +296,12 @@ impl VDiff for VComp {
                             _ => Reform::Before(None),
                         }
                     } else {
-                        Reform::Before(vcomp.detach(parent))
+                        Reform::Before(vcomp.detach(backend, parent))
                     }
                 }
-                Some(mut vnode) => Reform::Before(vnode.detach(parent)),
+                // Let the component diff its view against server-rendered markup
+                Some(vnode) if vnode.is_hydrated() => Reform::Adopt(vnode),
+                Some(mut vnode) => Reform::Before(vnode.detach(backend, parent)),
                 None => Reform::Before(None),
             };
 
@@ -206,42 +310,46 @@ impl VDiff for VComp {
                     // Send properties update when the component is already rendered.
                     this.replace(mounted)
                 }
+                Reform::Adopt(ancestor) => {
+                    this.mount(backend.element_handle(parent.to_owned()), ancestor)
+                }
                 Reform::Before(next_sibling) => {
-                    let dummy_node = document().create_text_node("");
-                    if let Some(next_sibling) = next_sibling {
-                        let next_sibling = &next_sibling;
-                        #[cfg(feature = "web_sys")]
-                        let next_sibling = Some(next_sibling);
-                        parent
-                            .insert_before(&dummy_node, next_sibling)
-                            .expect("can't insert dummy component node before next sibling");
-                    } else if let Some(next_sibling) =
-                        previous_sibling.and_then(|p| p.next_sibling())
-                    {
-                        let next_sibling = &next_sibling;
-                        #[cfg(feature = "web_sys")]
-                        let next_sibling = Some(next_sibling);
-                        parent
-                            .insert_before(&dummy_node, next_sibling)
-                            .expect("can't insert dummy component node before next sibling");
-                    } else {
-                        #[cfg_attr(
-                            feature = "std_web",
-                            allow(clippy::let_unit_value, unused_variables)
-                        )]
-                        {
-                            let result = parent.append_child(&dummy_node);
-                            #[cfg(feature = "web_sys")]
-                            result.expect("can't append node to parent");
-                        }
-                    }
-                    this.mount(parent.to_owned(), dummy_node)
+                    let dummy_node = backend.create_text("");
+                    let next_sibling = next_sibling
+                        .or_else(|| previous_sibling.and_then(|p| backend.next_sibling(p)));
+                    backend.insert_before(
+                        parent,
+                        &backend.text_node(&dummy_node),
+                        next_sibling.as_ref(),
+                    );
+                    // The component diffs its view against the placeholder
+                    let mut placeholder = VText::new(String::new());
+                    placeholder.reference = Some(backend.text_handle(dummy_node));
+                    this.mount(
+                        backend.element_handle(parent.to_owned()),
+                        placeholder.into(),
+                    )
                 }
             };
 
             self.state = MountState::Mounted(mounted);
         }
-        None
+        self.dom_node()
+            .map(|node| backend.handle_node(&node).clone())
+    }
+}
+
+impl VRender for VComp {
+    fn render_html(&self, html: &mut String) {
+        match &self.state {
+            MountState::Unmounted(this) => {
+                // The markers tell hydration which nodes belong to the component
+                write!(html, "<!--{}-->", COMPONENT_START).unwrap();
+                (this.renderer)(html);
+                write!(html, "<!--{}-->", COMPONENT_END).unwrap();
+            }
+            _ => warn!("Only unmounted components can be rendered to HTML"),
+        }
     }
 }
 
@@ -295,7 +403,7 @@ impl<'a> Transformer<Option<&'a str>, Option<String>> for VComp {
 
 impl PartialEq for VComp {
     fn eq(&self, other: &VComp) -> bool {
-        self.type_id == other.type_id
+        self.type_id == other.type_id && self.key == other.key
     }
 }
 
@@ -314,6 +422,7 @@ impl<COMP: Component> fmt::Debug for VChild<COMP> {
 #[cfg(test)]
 mod tests {
     use crate::macros::Properties;
+    use crate::virtual_dom::{DomBackend, MemoryDom, VDiff};
     use crate::{html, Component, ComponentLink, Html, ShouldRender};
     #[cfg(feature = "wasm_test")]
     use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
@@ -375,4 +484,151 @@ mod tests {
             <Comp with props />
         };
     }
+
+    struct Label {
+        props: LabelProps,
+    }
+
+    #[derive(Clone, PartialEq, Properties)]
+    struct LabelProps {
+        text: String,
+    }
+
+    impl Component for Label {
+        type Message = ();
+        type Properties = LabelProps;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            Label { props }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn change(&mut self, props: Self::Properties) -> ShouldRender {
+            self.props = props;
+            true
+        }
+
+        fn view(&self) -> Html {
+            html! { <span>{ self.props.text.clone() }</span> }
+        }
+    }
+
+    #[test]
+    fn updates_component_in_place() {
+        let dom = MemoryDom;
+        let parent = dom.create_element("div", None);
+
+        let mut ancestor = html! { <Label text="a" /> };
+        ancestor.apply(&dom, &parent, None, None);
+        assert_eq!(parent.text_content(), "a");
+        let nodes = ancestor.dom_nodes();
+        assert_eq!(nodes.len(), 1);
+
+        let mut vnode = html! { <Label text="b" /> };
+        vnode.apply(&dom, &parent, None, Some(ancestor));
+        assert_eq!(parent.text_content(), "b");
+        assert_eq!(vnode.dom_nodes(), nodes);
+
+        vnode.detach(&dom, &parent);
+        assert!(parent.children().is_empty());
+    }
+
+    #[test]
+    fn keyed_components_are_moved() {
+        let dom = MemoryDom;
+        let parent = dom.create_element("div", None);
+
+        let mut ancestor = html! {
+            <>
+                <Label key="a" text="a" />
+                <Label key="b" text="b" />
+                <Label key="c" text="c" />
+            </>
+        };
+        ancestor.apply(&dom, &parent, None, None);
+        assert_eq!(parent.text_content(), "abc");
+        let nodes = ancestor.dom_nodes();
+
+        let mut vnode = html! {
+            <>
+                <Label key="c" text="c" />
+                <Label key="a" text="a" />
+                <Label key="d" text="d" />
+            </>
+        };
+        vnode.apply(&dom, &parent, None, Some(ancestor));
+        assert_eq!(parent.text_content(), "cad");
+        assert_eq!(parent.children().len(), 3);
+        let moved = vnode.dom_nodes();
+        assert_eq!(moved[0], nodes[2]);
+        assert_eq!(moved[1], nodes[0]);
+        assert_ne!(moved[2], nodes[1]);
+    }
+
+    /// Renders its text twice, as a fragment.
+    struct Pair {
+        props: LabelProps,
+    }
+
+    impl Component for Pair {
+        type Message = ();
+        type Properties = LabelProps;
+
+        fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+            Pair { props }
+        }
+
+        fn update(&mut self, _: Self::Message) -> ShouldRender {
+            unimplemented!();
+        }
+
+        fn change(&mut self, props: Self::Properties) -> ShouldRender {
+            self.props = props;
+            true
+        }
+
+        fn view(&self) -> Html {
+            html! {
+                <>
+                    <b>{ self.props.text.clone() }</b>
+                    <i>{ self.props.text.clone() }</i>
+                </>
+            }
+        }
+    }
+
+    #[test]
+    fn keyed_components_are_moved_with_all_nodes() {
+        let dom = MemoryDom;
+        let parent = dom.create_element("div", None);
+
+        let mut ancestor = html! {
+            <>
+                <Pair key="a" text="a" />
+                <Pair key="b" text="b" />
+                <Pair key="c" text="c" />
+            </>
+        };
+        ancestor.apply(&dom, &parent, None, None);
+        assert_eq!(parent.text_content(), "aabbcc");
+        let nodes = ancestor.dom_nodes();
+        assert_eq!(nodes.len(), 6);
+
+        let mut vnode = html! {
+            <>
+                <Pair key="c" text="c" />
+                <Pair key="a" text="a" />
+                <Pair key="b" text="b" />
+            </>
+        };
+        vnode.apply(&dom, &parent, None, Some(ancestor));
+        assert_eq!(parent.text_content(), "ccaabb");
+        assert_eq!(parent.children().len(), 6);
+        let moved = vnode.dom_nodes();
+        assert_eq!(moved[..2], nodes[4..]);
+        assert_eq!(moved[2..], nodes[..4]);
+    }
 }
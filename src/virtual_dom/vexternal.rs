@@ -0,0 +1,238 @@
+//! This module contains the implementation of an externally managed element `VExternal`.
+
+use super::{backend, DomBackend, Reform, VDiff, VNode, VRender};
+use cfg_if::cfg_if;
+use log::warn;
+use std::fmt;
+use std::rc::Rc;
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        use stdweb::web::Element;
+    } else if #[cfg(feature = "web_sys")] {
+        use web_sys::Element;
+    }
+}
+
+/// A hook which is called with the element of a `VExternal`.
+type Hook = dyn Fn(&Element);
+
+/// A virtual node for an element which is managed outside of yew, e.g. by a code editor or
+/// a map library.
+///
+/// The element is created once and is preserved across renders as long as the key stays the
+/// same, so the widget which lives inside of it keeps its state. The `on_mount` hook is
+/// called after the element was inserted into the DOM and `on_unmount` before it's removed.
+pub struct VExternal {
+    /// The identity of the element. A new element is created when it changes.
+    pub key: String,
+    create: Rc<dyn Fn() -> Element>,
+    on_mount: Option<Rc<Hook>>,
+    on_unmount: Option<Rc<Hook>>,
+    /// A reference to the `Element`.
+    pub reference: Option<Element>,
+}
+
+impl VExternal {
+    /// Creates a node for the element returned by `create`, which is only called when the
+    /// element gets mounted for the first time.
+    pub fn new<F>(key: impl Into<String>, create: F) -> Self
+    where
+        F: Fn() -> Element + 'static,
+    {
+        VExternal {
+            key: key.into(),
+            create: Rc::new(create),
+            on_mount: None,
+            on_unmount: None,
+            reference: None,
+        }
+    }
+
+    /// Sets a hook which is called with the element after it was inserted into the DOM.
+    /// Use it to initialise the widget.
+    pub fn on_mount<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Element) + 'static,
+    {
+        self.on_mount = Some(Rc::new(hook));
+        self
+    }
+
+    /// Sets a hook which is called with the element before it's removed from the DOM.
+    /// Use it to tear the widget down.
+    pub fn on_unmount<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Element) + 'static,
+    {
+        self.on_unmount = Some(Rc::new(hook));
+        self
+    }
+}
+
+impl Clone for VExternal {
+    fn clone(&self) -> Self {
+        VExternal {
+            key: self.key.clone(),
+            create: self.create.clone(),
+            on_mount: self.on_mount.clone(),
+            on_unmount: self.on_unmount.clone(),
+            reference: None,
+        }
+    }
+}
+
+impl VDiff for VExternal {
+    /// Calls the `on_unmount` hook and removes the element from parent.
+    fn detach<B: DomBackend>(&mut self, backend: &B, parent: &B::Element) -> Option<B::Node> {
+        if !backend.is_browser() {
+            // The element is only rendered into the browser DOM
+            return None;
+        }
+        let element = self
+            .reference
+            .take()
+            .expect("tried to remove not rendered VExternal from DOM");
+        if let Some(on_unmount) = self.on_unmount.as_ref() {
+            on_unmount(&element);
+        }
+        let node = backend.element_node(&backend::browser_element(backend, &element)?);
+        let next_sibling = backend.next_sibling(&node);
+        if !backend.remove_child(parent, &node) {
+            warn!("Node not found to remove VExternal");
+        }
+        next_sibling
+    }
+
+    /// Keeps the element of the ancestor if it has the same key, otherwise creates a new one
+    /// and calls the `on_mount` hook.
+    fn apply<B: DomBackend>(
+        &mut self,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
+        ancestor: Option<VNode>,
+    ) -> Option<B::Node> {
+        assert!(
+            self.reference.is_none(),
+            "reference is ignored so must not be set"
+        );
+        let reform = match ancestor {
+            Some(VNode::VExternal(mut vexternal)) => {
+                if self.key == vexternal.key {
+                    // The hooks of the ancestor are replaced, but the element stays
+                    self.reference = vexternal.reference.take();
+                    Reform::Keep
+                } else {
+                    Reform::Before(vexternal.detach(backend, parent))
+                }
+            }
+            Some(mut vnode) => Reform::Before(vnode.detach(backend, parent)),
+            None => Reform::Before(None),
+        };
+
+        match reform {
+            // The element is a part of the browser DOM, so other backends leave it out
+            Reform::Before(_) if !backend.is_browser() => previous_sibling.cloned(),
+            Reform::Before(next_sibling) => {
+                let element = (self.create)();
+                let node = backend.element_node(&backend::browser_element(backend, &element)?);
+                let next_sibling =
+                    next_sibling.or_else(|| previous_sibling.and_then(|p| backend.next_sibling(p)));
+                backend.insert_before(parent, &node, next_sibling.as_ref());
+                if let Some(on_mount) = self.on_mount.as_ref() {
+                    on_mount(&element);
+                }
+                self.reference = Some(element);
+                Some(node)
+            }
+            Reform::Keep => self
+                .reference
+                .as_ref()
+                .and_then(|element| backend::browser_element(backend, element))
+                .map(|element| backend.element_node(&element))
+                .or_else(|| previous_sibling.cloned()),
+        }
+    }
+}
+
+impl VRender for VExternal {
+    fn render_html(&self, _html: &mut String) {
+        warn!("VExternal can't be rendered to HTML without a DOM");
+    }
+}
+
+impl From<VExternal> for VNode {
+    fn from(vexternal: VExternal) -> Self {
+        VNode::VExternal(vexternal)
+    }
+}
+
+impl fmt::Debug for VExternal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VExternal {{ key: {} }}", self.key)
+    }
+}
+
+impl PartialEq for VExternal {
+    fn eq(&self, other: &VExternal) -> bool {
+        self.key == other.key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html;
+    use crate::utils::document;
+    use crate::virtual_dom::Browser;
+    use std::cell::Cell;
+    #[cfg(feature = "std_web")]
+    use stdweb::web::INode;
+
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn editor(key: &str, mounts: &Rc<Cell<u32>>, unmounts: &Rc<Cell<u32>>) -> VNode {
+        let mounts = mounts.clone();
+        let unmounts = unmounts.clone();
+        let vexternal = VExternal::new(key, || document().create_element("pre").unwrap())
+            .on_mount(move |_| mounts.set(mounts.get() + 1))
+            .on_unmount(move |_| unmounts.set(unmounts.get() + 1));
+        html! { <div>{ vexternal }</div> }
+    }
+
+    #[test]
+    fn preserves_element_with_same_key() {
+        let parent = document().create_element("div").unwrap();
+        let mounts = Rc::new(Cell::new(0));
+        let unmounts = Rc::new(Cell::new(0));
+
+        let mut ancestor = editor("a", &mounts, &unmounts);
+        ancestor.apply(&Browser, &parent, None, None);
+        let element = parent.first_child().unwrap().first_child().unwrap();
+        assert_eq!((mounts.get(), unmounts.get()), (1, 0));
+
+        let mut vnode = editor("a", &mounts, &unmounts);
+        vnode.apply(&Browser, &parent, None, Some(ancestor));
+        assert_eq!(
+            parent.first_child().unwrap().first_child().unwrap(),
+            element
+        );
+        assert_eq!((mounts.get(), unmounts.get()), (1, 0));
+
+        let ancestor = vnode;
+        let mut vnode = editor("b", &mounts, &unmounts);
+        vnode.apply(&Browser, &parent, None, Some(ancestor));
+        assert_ne!(
+            parent.first_child().unwrap().first_child().unwrap(),
+            element
+        );
+        assert_eq!((mounts.get(), unmounts.get()), (2, 1));
+
+        vnode.detach(&Browser, &parent);
+        assert_eq!((mounts.get(), unmounts.get()), (2, 2));
+    }
+}
@@ -1,10 +1,14 @@
 //! This module contains fragments implementation.
-use super::{VDiff, VNode, VText};
+use super::vcomp::{COMPONENT_END, COMPONENT_START};
+use super::{backend, Browser, DomBackend, VDiff, VNode, VRender, VText};
 use cfg_if::cfg_if;
+use cfg_match::cfg_match;
+use log::warn;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::{Deref, DerefMut};
 cfg_if! {
     if #[cfg(feature = "std_web")] {
-        use stdweb::web::{Element, Node};
+        use stdweb::web::{Element, INode, Node, NodeType};
     } else if #[cfg(feature = "web_sys")] {
         use web_sys::{Element, Node};
     }
@@ -17,6 +21,8 @@ pub struct VList {
     pub children: Vec<VNode>,
     /// Never use a placeholder element if set to true.
     elide_placeholder: bool,
+    /// Set if the children were adopted from server-rendered markup.
+    hydrated: bool,
 }
 
 impl Deref for VList {
@@ -44,6 +50,7 @@ impl VList {
         VList {
             children,
             elide_placeholder: false,
+            hydrated: false,
         }
     }
 
@@ -52,6 +59,54 @@ impl VList {
         VList {
             children: Vec::new(),
             elide_placeholder: true,
+            hydrated: false,
+        }
+    }
+
+    /// Creates a `VList` from the existing children of `parent`, so that the next `apply`
+    /// adopts their DOM nodes instead of creating new ones.
+    ///
+    /// The nodes between the marker comments of a component are grouped into a nested list,
+    /// which the component adopts as a whole. The markers are removed.
+    pub(crate) fn adopt_children(parent: &Element) -> Self {
+        let mut groups = vec![Vec::new()];
+        let mut next_child = parent.first_child();
+        while let Some(child) = next_child {
+            next_child = child.next_sibling();
+            match component_marker(&child) {
+                Some(true) => groups.push(Vec::new()),
+                Some(false) => close_group(&mut groups),
+                None => {
+                    if let Some(vnode) = VNode::adopt(child) {
+                        groups.last_mut().unwrap().push(vnode);
+                    }
+                    continue;
+                }
+            }
+            Browser.remove_child(parent, &child);
+        }
+        while groups.len() > 1 {
+            close_group(&mut groups);
+        }
+        VList::adopted(groups.pop().unwrap())
+    }
+
+    /// Returns the only child of adopted nodes, or the list itself, as the ancestor of the
+    /// view of the component which rendered them.
+    pub(crate) fn into_view_ancestor(mut self) -> VNode {
+        if self.children.len() == 1 {
+            self.children.pop().unwrap()
+        } else {
+            VNode::VList(self)
+        }
+    }
+
+    /// Creates a `VList` of nodes adopted from server-rendered markup.
+    fn adopted(children: Vec<VNode>) -> Self {
+        VList {
+            children,
+            elide_placeholder: true,
+            hydrated: true,
         }
     }
 
@@ -59,33 +114,310 @@ impl VList {
     pub fn add_child(&mut self, child: VNode) {
         self.children.push(child);
     }
+
+    /// Returns true if any of the children has a key, in which case the children
+    /// are reconciled by key instead of by position.
+    fn is_keyed(&self) -> bool {
+        self.children.iter().any(|child| child.key().is_some())
+    }
+
+    /// Diffs children against their ancestors by position.
+    fn apply_unkeyed<B: DomBackend>(
+        &mut self,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<B::Node>,
+        mut rights: Vec<VNode>,
+    ) -> Option<B::Node> {
+        let mut previous_sibling = previous_sibling;
+        let mut lefts = self.children.iter_mut();
+        let mut rights = rights.drain(..);
+        loop {
+            match (lefts.next(), rights.next()) {
+                (Some(left), Some(right)) => {
+                    previous_sibling =
+                        left.apply(backend, parent, previous_sibling.as_ref(), Some(right));
+                }
+                (Some(left), None) => {
+                    previous_sibling = left.apply(backend, parent, previous_sibling.as_ref(), None);
+                }
+                (None, Some(ref mut right)) => {
+                    right.detach(backend, parent);
+                }
+                (None, None) => break,
+            }
+        }
+        previous_sibling
+    }
+
+    /// Returns true if the children were adopted from server-rendered markup.
+    pub(crate) fn is_hydrated(&self) -> bool {
+        self.hydrated
+    }
+
+    /// Diffs children against nodes adopted from server-rendered markup.
+    ///
+    /// Adopted nodes don't have keys and nested fragments are flattened in the DOM, so each
+    /// child takes as many of the adopted nodes as it renders at the top level. Components
+    /// take the group of nodes between their markers.
+    fn apply_hydrated<B: DomBackend>(
+        &mut self,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<B::Node>,
+        rights: Vec<VNode>,
+    ) -> Option<B::Node> {
+        let children_nodes: Vec<Vec<Rendered>> = self
+            .children
+            .iter_mut()
+            .map(|child| {
+                let mut nodes = Vec::new();
+                rendered_nodes(child, &mut nodes);
+                nodes
+            })
+            .collect();
+        let mut rights: VecDeque<VNode> = rights.into();
+        split_merged_texts(children_nodes.iter().flatten(), &mut rights);
+
+        let mut previous_sibling = previous_sibling;
+        for (left, nodes) in self.children.iter_mut().zip(children_nodes) {
+            let right = VList::take_adopted(backend, left, nodes.len(), parent, &mut rights);
+            match (&right, rendered(left)) {
+                // Empty fragments render a placeholder which doesn't exist in the markup
+                (None, VNode::VText(vtext)) if vtext.text.is_empty() => {}
+                (None, VNode::VPortal(_))
+                | (None, VNode::VRaw(_))
+                | (None, VNode::VExternal(_))
+                | (None, VNode::VRef(_)) => {}
+                (None, _) if nodes.is_empty() => {}
+                (None, _) => warn!("Hydration mismatch: missing node for {:?}", left),
+                _ => {}
+            }
+            previous_sibling = left.apply(backend, parent, previous_sibling.as_ref(), right);
+        }
+        for mut right in rights {
+            warn!("Hydration mismatch: removing unexpected node {:?}", right);
+            right.detach(backend, parent);
+        }
+        previous_sibling
+    }
+
+    /// Takes the `count` adopted nodes which `child` renders to on the server.
+    fn take_adopted<B: DomBackend>(
+        backend: &B,
+        child: &mut VNode,
+        count: usize,
+        parent: &B::Element,
+        rights: &mut VecDeque<VNode>,
+    ) -> Option<VNode> {
+        let count = count.min(rights.len());
+        match child {
+            // Raw markup is parsed again instead of adopting its nodes
+            VNode::VRaw(_) => {
+                for mut right in rights.drain(..count) {
+                    right.detach(backend, parent);
+                }
+                None
+            }
+            VNode::VMemo(vmemo) => {
+                VList::take_adopted(backend, vmemo.node_mut(), count, parent, rights)
+            }
+            // Fragments, also the ones components render, get their nodes as a fragment
+            VNode::VList(_) => Some(VNode::VList(VList::adopted(
+                rights.drain(..count).collect(),
+            ))),
+            VNode::VComp(_) => rights.pop_front().map(|right| match right {
+                VNode::VList(group) if group.hydrated => group.into_view_ancestor(),
+                right => right,
+            }),
+            _ if count == 1 => rights.pop_front(),
+            _ => None,
+        }
+    }
+
+    /// Diffs children against the ancestor with the same key. Children without a key are
+    /// matched with the remaining unkeyed ancestors by position.
+    ///
+    /// Matched ancestors keep their DOM nodes, which are moved into the new order instead
+    /// of being recreated. Only the ancestors which weren't matched are detached.
+    fn apply_keyed<B: DomBackend>(
+        &mut self,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<B::Node>,
+        rights: Vec<VNode>,
+    ) -> Option<B::Node> {
+        // The node in front of which the next child has to end up.
+        let mut next_sibling = match previous_sibling {
+            Some(ref node) => backend.next_sibling(node),
+            None => rights
+                .iter()
+                .flat_map(VNode::dom_nodes)
+                .find_map(|node| backend::owned_node(backend, &node)),
+        };
+
+        let mut keyed_rights = HashMap::new();
+        let mut unkeyed_rights = VecDeque::new();
+        for right in rights {
+            match right.key().map(str::to_owned) {
+                Some(key) => {
+                    if let Some(mut duplicate) = keyed_rights.insert(key, right) {
+                        duplicate.detach(backend, parent);
+                    }
+                }
+                None => unkeyed_rights.push_back(right),
+            }
+        }
+
+        let mut seen_keys = HashSet::new();
+        let mut previous_sibling = previous_sibling;
+        for left in self.children.iter_mut() {
+            let right = match left.key() {
+                Some(key) => {
+                    if !seen_keys.insert(key.to_owned()) {
+                        warn!("Duplicate key `{}` in a list of children", key);
+                    }
+                    keyed_rights.remove(key)
+                }
+                None => unkeyed_rights.pop_front(),
+            };
+            let last_node = left.apply(backend, parent, previous_sibling.as_ref(), right);
+
+            // Make sure the nodes of this child directly follow the previous child.
+            let nodes: Vec<B::Node> = left
+                .dom_nodes()
+                .iter()
+                .filter_map(|node| backend::owned_node(backend, node))
+                .collect();
+            if let (Some(first), Some(last)) = (nodes.first(), nodes.last()) {
+                if Some(first) == next_sibling.as_ref() {
+                    next_sibling = backend.next_sibling(last);
+                } else if backend.next_sibling(last) != next_sibling {
+                    for node in &nodes {
+                        backend.insert_before(parent, node, next_sibling.as_ref());
+                    }
+                }
+            }
+            previous_sibling = last_node.or_else(|| nodes.last().cloned());
+        }
+
+        for (_, mut right) in keyed_rights {
+            right.detach(backend, parent);
+        }
+        for mut right in unkeyed_rights {
+            right.detach(backend, parent);
+        }
+        previous_sibling
+    }
+}
+
+/// Returns the subtree a memoized node rendered, or the node itself.
+fn rendered(node: &VNode) -> &VNode {
+    match node {
+        VNode::VMemo(vmemo) => vmemo.node().map(rendered).unwrap_or(node),
+        _ => node,
+    }
+}
+
+/// A top level node of the markup a subtree renders to on the server.
+enum Rendered {
+    Text(String),
+    Node,
+}
+
+/// Collects the top level nodes `node` renders to on the server, flattening fragments. A
+/// component is a single group of nodes.
+fn rendered_nodes(node: &mut VNode, nodes: &mut Vec<Rendered>) {
+    match node {
+        VNode::VText(vtext) if vtext.text.is_empty() => {}
+        VNode::VText(vtext) => nodes.push(Rendered::Text(vtext.text.clone())),
+        VNode::VList(vlist) => {
+            for child in vlist.children.iter_mut() {
+                rendered_nodes(child, nodes);
+            }
+        }
+        VNode::VRaw(vraw) => nodes.extend((0..vraw.node_count()).map(|_| Rendered::Node)),
+        VNode::VMemo(vmemo) => rendered_nodes(vmemo.node_mut(), nodes),
+        VNode::VSuspense(vsuspense) => rendered_nodes(vsuspense.content_mut(), nodes),
+        // These nodes are not a part of the markup
+        VNode::VPortal(_) | VNode::VExternal(_) | VNode::VRef(_) => {}
+        VNode::VTag(_) | VNode::VComp(_) => nodes.push(Rendered::Node),
+    }
+}
+
+/// Returns `Some(true)` for the comment which opens the markup of a component and
+/// `Some(false)` for the one which closes it.
+fn component_marker(node: &Node) -> Option<bool> {
+    let is_comment = cfg_match! {
+        feature = "std_web" => node.node_type() == NodeType::Comment,
+        feature = "web_sys" => node.node_type() == Node::COMMENT_NODE,
+    };
+    if !is_comment {
+        return None;
+    }
+    match node.text_content().as_deref() {
+        Some(COMPONENT_START) => Some(true),
+        Some(COMPONENT_END) => Some(false),
+        _ => None,
+    }
+}
+
+/// Moves the innermost group of adopted nodes into the enclosing one.
+fn close_group(groups: &mut Vec<Vec<VNode>>) {
+    if groups.len() > 1 {
+        let group = groups.pop().unwrap();
+        groups
+            .last_mut()
+            .unwrap()
+            .push(VNode::VList(VList::adopted(group)));
+    }
+}
+
+/// Splits the adopted text nodes which hold several adjacent texts, so that every text is
+/// matched with a node of its own.
+fn split_merged_texts<'a>(
+    rendered: impl Iterator<Item = &'a Rendered>,
+    rights: &mut VecDeque<VNode>,
+) {
+    for (index, rendered) in rendered.enumerate() {
+        if let (Rendered::Text(text), Some(VNode::VText(vtext))) = (rendered, rights.get_mut(index))
+        {
+            if let Some(rest) = vtext.split_adopted(text) {
+                rights.insert(index + 1, VNode::VText(rest));
+            }
+        }
+    }
 }
 
 impl VDiff for VList {
-    fn detach(&mut self, parent: &Element) -> Option<Node> {
+    fn detach<B: DomBackend>(&mut self, backend: &B, parent: &B::Element) -> Option<B::Node> {
         let mut next_sibling = None;
         for mut child in self.children.drain(..) {
-            next_sibling = child.detach(parent);
+            next_sibling = child.detach(backend, parent);
         }
         next_sibling
     }
 
-    fn apply(
+    fn apply<B: DomBackend>(
         &mut self,
-        parent: &Element,
-        previous_sibling: Option<&Node>,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
         ancestor: Option<VNode>,
-    ) -> Option<Node> {
+    ) -> Option<B::Node> {
         // Reuse previous_sibling, because fragment reuse parent
-        let mut previous_sibling = previous_sibling.cloned();
-        let mut rights = {
+        let previous_sibling = previous_sibling.cloned();
+        let mut hydrating = false;
+        let rights = {
             match ancestor {
                 // If element matched this type
                 Some(VNode::VList(vlist)) => {
+                    hydrating = vlist.hydrated;
                     // Previously rendered items
                     vlist.children
                 }
                 Some(vnode) => {
+                    hydrating = vnode.is_hydrated();
                     // Use the current node as a single fragment list
                     // and let the `apply` of `VNode` to handle it.
                     vec![vnode]
@@ -103,28 +435,27 @@ impl VDiff for VList {
         }
 
         // Process children
-        let mut lefts = self.children.iter_mut();
-        let mut rights = rights.drain(..);
-        loop {
-            match (lefts.next(), rights.next()) {
-                (Some(left), Some(right)) => {
-                    previous_sibling = left.apply(parent, previous_sibling.as_ref(), Some(right));
-                }
-                (Some(left), None) => {
-                    previous_sibling = left.apply(parent, previous_sibling.as_ref(), None);
-                }
-                (None, Some(ref mut right)) => {
-                    right.detach(parent);
-                }
-                (None, None) => break,
-            }
+        if hydrating {
+            self.apply_hydrated(backend, parent, previous_sibling, rights)
+        } else if self.is_keyed() {
+            self.apply_keyed(backend, parent, previous_sibling, rights)
+        } else {
+            self.apply_unkeyed(backend, parent, previous_sibling, rights)
+        }
+    }
+}
+
+impl VRender for VList {
+    fn render_html(&self, html: &mut String) {
+        for child in &self.children {
+            child.render_html(html);
         }
-        previous_sibling
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::virtual_dom::{DomBackend, MemoryDom, VDiff};
     use crate::{html, Component, ComponentLink, Html, ShouldRender};
     #[cfg(feature = "wasm_test")]
     use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
@@ -151,6 +482,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn keyed_children_are_moved() {
+        let dom = MemoryDom;
+        let parent = dom.create_element("div", None);
+
+        let mut ancestor = html! {
+            <>
+                <span key="a">{ "a" }</span>
+                <span key="b">{ "b" }</span>
+                <span key="c">{ "c" }</span>
+            </>
+        };
+        ancestor.apply(&dom, &parent, None, None);
+        let nodes = ancestor.dom_nodes();
+
+        let mut vnode = html! {
+            <>
+                <span key="c">{ "c" }</span>
+                <span key="a">{ "a" }</span>
+                <span key="d">{ "d" }</span>
+            </>
+        };
+        vnode.apply(&dom, &parent, None, Some(ancestor));
+
+        assert_eq!(parent.text_content(), "cad");
+        let moved = vnode.dom_nodes();
+        assert_eq!(moved[0], nodes[2]);
+        assert_eq!(moved[1], nodes[0]);
+        assert_ne!(moved[2], nodes[1]);
+    }
+
     #[test]
     fn check_fragments() {
         let fragment = html! {
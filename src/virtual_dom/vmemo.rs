@@ -0,0 +1,193 @@
+//! This module contains the implementation of a memoized subtree `VMemo`.
+
+use super::{backend, DomBackend, VDiff, VNode, VRender};
+use std::any::Any;
+use std::fmt;
+use std::rc::Rc;
+
+/// A virtual node which renders its content only when its dependencies change.
+///
+/// If the dependencies are equal to the dependencies of the ancestor, the rendered subtree
+/// and the DOM of the ancestor are reused as they are: the closure isn't called and the
+/// children aren't diffed.
+#[derive(Clone)]
+pub struct VMemo {
+    deps: Rc<dyn Any>,
+    deps_eq: fn(&dyn Any, &dyn Any) -> bool,
+    render: Rc<dyn Fn() -> VNode>,
+    node: Option<Box<VNode>>,
+}
+
+impl VMemo {
+    /// Creates a node which renders `render(&deps)` lazily.
+    ///
+    /// The closure must be `'static`, so it should take everything it needs from the
+    /// dependencies rather than from the component.
+    ///
+    /// ```ignore
+    /// html! {
+    ///     <ul>
+    ///         { VMemo::new(self.items.clone(), |items| {
+    ///             items.iter().map(|item| html! { <li>{ item }</li> }).collect::<Html>()
+    ///         }) }
+    ///     </ul>
+    /// }
+    /// ```
+    pub fn new<D, F>(deps: D, render: F) -> Self
+    where
+        D: PartialEq + 'static,
+        F: Fn(&D) -> VNode + 'static,
+    {
+        let deps = Rc::new(deps);
+        let render_deps = deps.clone();
+        VMemo {
+            deps,
+            deps_eq: |this, other| match (this.downcast_ref::<D>(), other.downcast_ref::<D>()) {
+                (Some(this), Some(other)) => this == other,
+                _ => false,
+            },
+            render: Rc::new(move || render(&render_deps)),
+            node: None,
+        }
+    }
+
+    /// Returns the rendered subtree, rendering it if that didn't happen yet.
+    pub(crate) fn node_mut(&mut self) -> &mut VNode {
+        let render = &self.render;
+        self.node.get_or_insert_with(|| Box::new(render()))
+    }
+
+    /// Returns the rendered subtree if it was rendered.
+    pub(crate) fn node(&self) -> Option<&VNode> {
+        self.node.as_deref()
+    }
+
+    fn deps_eq(&self, other: &VMemo) -> bool {
+        (self.deps_eq)(&*self.deps, &*other.deps)
+    }
+}
+
+impl VDiff for VMemo {
+    /// Remove the rendered subtree from parent.
+    fn detach<B: DomBackend>(&mut self, backend: &B, parent: &B::Element) -> Option<B::Node> {
+        self.node
+            .as_mut()
+            .and_then(|node| node.detach(backend, parent))
+    }
+
+    /// Reuses the subtree of the ancestor if the dependencies didn't change. Otherwise
+    /// renders the subtree and diffs it against the subtree of the ancestor.
+    fn apply<B: DomBackend>(
+        &mut self,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
+        ancestor: Option<VNode>,
+    ) -> Option<B::Node> {
+        let ancestor = match ancestor {
+            Some(VNode::VMemo(mut vmemo)) => {
+                if self.node.is_none() && vmemo.node.is_some() && self.deps_eq(&vmemo) {
+                    self.node = vmemo.node.take();
+                    let last = self.node().and_then(|node| {
+                        let nodes = node.dom_nodes();
+                        nodes
+                            .iter()
+                            .rev()
+                            .find_map(|node| backend::owned_node(backend, node))
+                    });
+                    return last.or_else(|| previous_sibling.cloned());
+                }
+                vmemo.node.map(|node| *node)
+            }
+            ancestor => ancestor,
+        };
+        self.node_mut()
+            .apply(backend, parent, previous_sibling, ancestor)
+    }
+}
+
+impl VRender for VMemo {
+    fn render_html(&self, html: &mut String) {
+        match self.node() {
+            Some(node) => node.render_html(html),
+            None => (self.render)().render_html(html),
+        }
+    }
+}
+
+impl From<VMemo> for VNode {
+    fn from(vmemo: VMemo) -> Self {
+        VNode::VMemo(vmemo)
+    }
+}
+
+impl fmt::Debug for VMemo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VMemo {{ node: {:?} }}", self.node)
+    }
+}
+
+impl PartialEq for VMemo {
+    fn eq(&self, other: &VMemo) -> bool {
+        self.deps_eq(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html;
+    use crate::utils::document;
+    use crate::virtual_dom::Browser;
+    use std::cell::Cell;
+    #[cfg(feature = "std_web")]
+    use stdweb::web::INode;
+
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn counted(value: u32, renders: &Rc<Cell<u32>>) -> VNode {
+        let renders = renders.clone();
+        let vmemo = VMemo::new(value, move |value| {
+            renders.set(renders.get() + 1);
+            html! { <p>{ value }</p> }
+        });
+        html! { <div>{ vmemo }</div> }
+    }
+
+    #[test]
+    fn compares_dependencies() {
+        let a = VMemo::new(1, |_| html! {});
+        assert_eq!(a, VMemo::new(1, |_| html! { <p /> }));
+        assert_ne!(a, VMemo::new(2, |_| html! {}));
+        assert_ne!(a, VMemo::new("1", |_| html! {}));
+    }
+
+    #[test]
+    fn skips_render_with_same_dependencies() {
+        let parent = document().create_element("div").unwrap();
+        let renders = Rc::new(Cell::new(0));
+
+        let mut ancestor = counted(1, &renders);
+        ancestor.apply(&Browser, &parent, None, None);
+        let element = parent.first_child().unwrap().first_child().unwrap();
+        assert_eq!(renders.get(), 1);
+
+        let mut vnode = counted(1, &renders);
+        vnode.apply(&Browser, &parent, None, Some(ancestor));
+        assert_eq!(
+            parent.first_child().unwrap().first_child().unwrap(),
+            element
+        );
+        assert_eq!(renders.get(), 1);
+
+        let ancestor = vnode;
+        let mut vnode = counted(2, &renders);
+        vnode.apply(&Browser, &parent, None, Some(ancestor));
+        assert_eq!(parent.text_content().unwrap(), "2");
+        assert_eq!(renders.get(), 2);
+    }
+}
@@ -1,16 +1,22 @@
 //! This module contains the implementation of abstract virtual node.
 
-use super::{VChild, VComp, VDiff, VList, VTag, VText};
+use super::{
+    backend, Allowlist, Browser, DomBackend, Handle, MemoryDom, VChild, VComp, VDiff, VExternal,
+    VList, VMemo, VPortal, VRaw, VRender, VSuspense, VTag, VText,
+};
 use crate::html::{Component, Renderable};
 use cfg_if::cfg_if;
+use cfg_match::cfg_match;
 use log::warn;
 use std::cmp::PartialEq;
 use std::fmt;
 use std::iter::FromIterator;
 cfg_if! {
     if #[cfg(feature = "std_web")] {
-        use stdweb::web::{Element, INode, Node};
+        use stdweb::unstable::TryFrom;
+        use stdweb::web::{Element, Node, TextNode};
     } else if #[cfg(feature = "web_sys")] {
+        use wasm_bindgen::JsCast;
         use web_sys::{Element, Node};
     }
 }
@@ -28,19 +34,223 @@ pub enum VNode {
     VList(VList),
     /// A holder for any `Node` (necessary for replacing node).
     VRef(Node),
+    /// A bind between `VPortal` and a host `Element` elsewhere in the document.
+    VPortal(VPortal),
+    /// A bind between `VExternal` and an `Element` managed outside of yew.
+    VExternal(VExternal),
+    /// A bind between `VRaw` and the nodes its markup was parsed into.
+    VRaw(VRaw),
+    /// A bind between `VMemo` and the subtree it rendered.
+    VMemo(VMemo),
+    /// A bind between `VSuspense` and its children or fallback.
+    VSuspense(VSuspense),
+}
+
+impl VNode {
+    /// Creates a node which renders the `html` markup as is. The markup must come from a
+    /// trusted source, because it can run scripts, e.g. through `onerror` attributes.
+    /// Use `from_html_sanitized` for any other markup.
+    pub fn from_html_unchecked(html: impl Into<String>) -> Self {
+        VNode::VRaw(VRaw::new(html.into()))
+    }
+
+    /// Creates a node which renders the `html` markup after removing the tags and attributes
+    /// which are not in the default `Allowlist`.
+    pub fn from_html_sanitized(html: &str) -> Self {
+        VNode::VRaw(VRaw::new(Allowlist::default().sanitize(html)))
+    }
+
+    /// Returns the key used to match this node against its ancestor in a list of children.
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            VNode::VTag(vtag) => vtag.key.as_deref(),
+            VNode::VComp(vcomp) => vcomp.key.as_deref(),
+            VNode::VExternal(vexternal) => Some(&vexternal.key),
+            VNode::VText(_)
+            | VNode::VList(_)
+            | VNode::VRef(_)
+            | VNode::VPortal(_)
+            | VNode::VRaw(_)
+            | VNode::VMemo(_)
+            | VNode::VSuspense(_) => None,
+        }
+    }
+
+    /// Returns the top level DOM nodes rendered by this node, in document order.
+    pub(crate) fn dom_nodes(&self) -> Vec<Handle<Node>> {
+        match self {
+            VNode::VTag(vtag) => vtag
+                .reference
+                .as_ref()
+                .map(|element| match element {
+                    Handle::Browser(element) => Handle::Browser(Browser.element_node(element)),
+                    Handle::Memory(element) => Handle::Memory(element.clone()),
+                })
+                .into_iter()
+                .collect(),
+            VNode::VText(vtext) => vtext
+                .reference
+                .as_ref()
+                .map(|text| match text {
+                    Handle::Browser(text) => Handle::Browser(Browser.text_node(text)),
+                    Handle::Memory(text) => Handle::Memory(text.clone()),
+                })
+                .into_iter()
+                .collect(),
+            VNode::VComp(vcomp) => vcomp.dom_nodes(),
+            VNode::VList(vlist) => vlist.iter().flat_map(VNode::dom_nodes).collect(),
+            VNode::VRef(node) => vec![Handle::Browser(node.clone())],
+            // The content of a portal is rendered elsewhere
+            VNode::VPortal(_) => Vec::new(),
+            VNode::VRaw(vraw) => vraw.nodes().to_vec(),
+            VNode::VMemo(vmemo) => vmemo.node().map(VNode::dom_nodes).unwrap_or_default(),
+            VNode::VSuspense(vsuspense) => vsuspense.dom_nodes(),
+            VNode::VExternal(vexternal) => vexternal
+                .reference
+                .as_ref()
+                .map(|element| Handle::Browser(Browser.element_node(element)))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Applies the node to `parent` with the backend which created it, as the only content
+    /// of the parent rendered by yew.
+    pub(crate) fn apply_to(
+        &mut self,
+        parent: &Handle<Element>,
+        ancestor: Option<VNode>,
+    ) -> Option<Handle<Node>> {
+        match parent {
+            Handle::Browser(parent) => self
+                .apply(&Browser, parent, None, ancestor)
+                .map(Handle::Browser),
+            Handle::Memory(parent) => self
+                .apply(&MemoryDom, parent, None, ancestor)
+                .map(Handle::Memory),
+        }
+    }
+
+    /// Moves the DOM nodes rendered by this node to `next_parent`, in front of
+    /// `next_sibling`. Components inside of the node render into the new parent afterwards.
+    pub(crate) fn shift<B: DomBackend>(
+        &self,
+        backend: &B,
+        next_parent: &B::Element,
+        next_sibling: Option<&B::Node>,
+    ) {
+        match self {
+            VNode::VComp(vcomp) => vcomp.shift(backend, next_parent, next_sibling),
+            VNode::VList(vlist) => {
+                for child in vlist.iter() {
+                    child.shift(backend, next_parent, next_sibling);
+                }
+            }
+            VNode::VMemo(vmemo) => {
+                if let Some(node) = vmemo.node() {
+                    node.shift(backend, next_parent, next_sibling);
+                }
+            }
+            VNode::VSuspense(vsuspense) => vsuspense.shift(backend, next_parent, next_sibling),
+            // The content of a portal stays in its host
+            VNode::VPortal(_) => {}
+            VNode::VTag(_)
+            | VNode::VText(_)
+            | VNode::VRef(_)
+            | VNode::VRaw(_)
+            | VNode::VExternal(_) => {
+                for node in self.dom_nodes() {
+                    if let Some(node) = backend::owned_node(backend, &node) {
+                        backend.insert_before(next_parent, &node, next_sibling);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves the node to `parent` with the backend which created it, see `shift`.
+    pub(crate) fn shift_to(&self, parent: &Handle<Element>, next_sibling: Option<&Handle<Node>>) {
+        match parent {
+            Handle::Browser(parent) => {
+                let next_sibling = next_sibling.map(|node| Browser.handle_node(node));
+                self.shift(&Browser, parent, next_sibling);
+            }
+            Handle::Memory(parent) => {
+                let next_sibling = next_sibling.map(|node| MemoryDom.handle_node(node));
+                self.shift(&MemoryDom, parent, next_sibling);
+            }
+        }
+    }
+
+    /// Removes the node from `parent` with the backend which created it.
+    pub(crate) fn detach_from(&mut self, parent: &Handle<Element>) {
+        match parent {
+            Handle::Browser(parent) => {
+                self.detach(&Browser, parent);
+            }
+            Handle::Memory(parent) => {
+                self.detach(&MemoryDom, parent);
+            }
+        }
+    }
+
+    /// Creates a virtual node which represents an existing DOM node. Returns `None` for
+    /// nodes which `html!` can't produce, like comments.
+    pub(crate) fn adopt(node: Node) -> Option<VNode> {
+        cfg_match! {
+            feature = "std_web" => ({
+                if let Ok(element) = Element::try_from(node.clone()) {
+                    Some(VNode::VTag(Box::new(VTag::adopt(element))))
+                } else if let Ok(text_node) = TextNode::try_from(node) {
+                    Some(VNode::VText(VText::adopt(text_node)))
+                } else {
+                    None
+                }
+            }),
+            feature = "web_sys" => match node.node_type() {
+                Node::ELEMENT_NODE => {
+                    Some(VNode::VTag(Box::new(VTag::adopt(node.unchecked_into()))))
+                }
+                Node::TEXT_NODE => Some(VNode::VText(VText::adopt(node.unchecked_into()))),
+                _ => None,
+            },
+        }
+    }
+
+    /// Returns true if the node was adopted from server-rendered markup.
+    pub(crate) fn is_hydrated(&self) -> bool {
+        match self {
+            VNode::VTag(vtag) => vtag.is_hydrated(),
+            VNode::VText(vtext) => vtext.is_hydrated(),
+            VNode::VList(vlist) => vlist.is_hydrated(),
+            VNode::VComp(_)
+            | VNode::VRef(_)
+            | VNode::VPortal(_)
+            | VNode::VExternal(_)
+            | VNode::VRaw(_)
+            | VNode::VMemo(_)
+            | VNode::VSuspense(_) => false,
+        }
+    }
 }
 
 impl VDiff for VNode {
     /// Remove VNode from parent.
-    fn detach(&mut self, parent: &Element) -> Option<Node> {
+    fn detach<B: DomBackend>(&mut self, backend: &B, parent: &B::Element) -> Option<B::Node> {
         match *self {
-            VNode::VTag(ref mut vtag) => vtag.detach(parent),
-            VNode::VText(ref mut vtext) => vtext.detach(parent),
-            VNode::VComp(ref mut vcomp) => vcomp.detach(parent),
-            VNode::VList(ref mut vlist) => vlist.detach(parent),
+            VNode::VTag(ref mut vtag) => vtag.detach(backend, parent),
+            VNode::VText(ref mut vtext) => vtext.detach(backend, parent),
+            VNode::VComp(ref mut vcomp) => vcomp.detach(backend, parent),
+            VNode::VList(ref mut vlist) => vlist.detach(backend, parent),
+            VNode::VPortal(ref mut vportal) => vportal.detach(backend, parent),
+            VNode::VExternal(ref mut vexternal) => vexternal.detach(backend, parent),
+            VNode::VRaw(ref mut vraw) => vraw.detach(backend, parent),
+            VNode::VMemo(ref mut vmemo) => vmemo.detach(backend, parent),
+            VNode::VSuspense(ref mut vsuspense) => vsuspense.detach(backend, parent),
             VNode::VRef(ref node) => {
-                let sibling = node.next_sibling();
-                if parent.remove_child(node).is_err() {
+                let node = backend::browser_node(backend, node)?;
+                let sibling = backend.next_sibling(&node);
+                if !backend.remove_child(parent, &node) {
                     warn!("Node not found to remove VRef");
                 }
                 sibling
@@ -48,47 +258,64 @@ impl VDiff for VNode {
         }
     }
 
-    fn apply(
+    fn apply<B: DomBackend>(
         &mut self,
-        parent: &Element,
-        previous_sibling: Option<&Node>,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
         ancestor: Option<VNode>,
-    ) -> Option<Node> {
+    ) -> Option<B::Node> {
         match *self {
-            VNode::VTag(ref mut vtag) => vtag.apply(parent, previous_sibling, ancestor),
-            VNode::VText(ref mut vtext) => vtext.apply(parent, previous_sibling, ancestor),
-            VNode::VComp(ref mut vcomp) => vcomp.apply(parent, previous_sibling, ancestor),
-            VNode::VList(ref mut vlist) => vlist.apply(parent, previous_sibling, ancestor),
+            VNode::VTag(ref mut vtag) => vtag.apply(backend, parent, previous_sibling, ancestor),
+            VNode::VText(ref mut vtext) => vtext.apply(backend, parent, previous_sibling, ancestor),
+            VNode::VComp(ref mut vcomp) => vcomp.apply(backend, parent, previous_sibling, ancestor),
+            VNode::VList(ref mut vlist) => vlist.apply(backend, parent, previous_sibling, ancestor),
+            VNode::VPortal(ref mut vportal) => {
+                vportal.apply(backend, parent, previous_sibling, ancestor)
+            }
+            VNode::VExternal(ref mut vexternal) => {
+                vexternal.apply(backend, parent, previous_sibling, ancestor)
+            }
+            VNode::VRaw(ref mut vraw) => vraw.apply(backend, parent, previous_sibling, ancestor),
+            VNode::VMemo(ref mut vmemo) => vmemo.apply(backend, parent, previous_sibling, ancestor),
+            VNode::VSuspense(ref mut vsuspense) => {
+                vsuspense.apply(backend, parent, previous_sibling, ancestor)
+            }
             VNode::VRef(ref mut node) => {
                 let sibling = match ancestor {
-                    Some(mut n) => n.detach(parent),
+                    Some(mut n) => n.detach(backend, parent),
                     None => None,
                 };
-                if let Some(sibling) = sibling {
-                    let sibling = &sibling;
-                    #[cfg(feature = "web_sys")]
-                    let sibling = Some(sibling);
-                    parent
-                        .insert_before(node, sibling)
-                        .expect("can't insert element before sibling");
-                } else {
-                    #[cfg_attr(
-                        feature = "std_web",
-                        allow(clippy::let_unit_value, unused_variables)
-                    )]
-                    {
-                        let result = parent.append_child(node);
-                        #[cfg(feature = "web_sys")]
-                        result.expect("can't append node to parent");
+                // The node is a part of the browser DOM, so other backends leave it out
+                match backend::browser_node(backend, node) {
+                    Some(node) => {
+                        backend.insert_before(parent, &node, sibling.as_ref());
+                        Some(node)
                     }
+                    None => previous_sibling.cloned(),
                 }
-
-                Some(node.to_owned())
             }
         }
     }
 }
 
+impl VRender for VNode {
+    fn render_html(&self, html: &mut String) {
+        match self {
+            VNode::VTag(vtag) => vtag.render_html(html),
+            VNode::VText(vtext) => vtext.render_html(html),
+            VNode::VComp(vcomp) => vcomp.render_html(html),
+            VNode::VList(vlist) => vlist.render_html(html),
+            VNode::VPortal(vportal) => vportal.render_html(html),
+            VNode::VExternal(vexternal) => vexternal.render_html(html),
+            VNode::VRaw(vraw) => vraw.render_html(html),
+            VNode::VMemo(vmemo) => vmemo.render_html(html),
+            VNode::VSuspense(vsuspense) => vsuspense.render_html(html),
+            VNode::VRef(_) => warn!("VRef can't be rendered to HTML without a DOM"),
+        }
+    }
+}
+
 impl Default for VNode {
     fn default() -> Self {
         VNode::VList(VList::default())
@@ -158,6 +385,11 @@ impl fmt::Debug for VNode {
             VNode::VComp(_) => "Component<>".fmt(f),
             VNode::VList(_) => "List<>".fmt(f),
             VNode::VRef(_) => "NodeReference<>".fmt(f),
+            VNode::VPortal(ref vportal) => vportal.fmt(f),
+            VNode::VExternal(ref vexternal) => vexternal.fmt(f),
+            VNode::VRaw(ref vraw) => vraw.fmt(f),
+            VNode::VMemo(ref vmemo) => vmemo.fmt(f),
+            VNode::VSuspense(ref vsuspense) => vsuspense.fmt(f),
         }
     }
 }
@@ -169,6 +401,11 @@ impl PartialEq for VNode {
             (VNode::VText(a), VNode::VText(b)) => a == b,
             (VNode::VList(a), VNode::VList(b)) => a == b,
             (VNode::VRef(a), VNode::VRef(b)) => a == b,
+            (VNode::VPortal(a), VNode::VPortal(b)) => a == b,
+            (VNode::VExternal(a), VNode::VExternal(b)) => a == b,
+            (VNode::VRaw(a), VNode::VRaw(b)) => a == b,
+            (VNode::VMemo(a), VNode::VMemo(b)) => a == b,
+            (VNode::VSuspense(a), VNode::VSuspense(b)) => a == b,
             // Need to improve PartialEq for VComp before enabling
             (VNode::VComp(_), VNode::VComp(_)) => false,
             _ => false,
@@ -0,0 +1,189 @@
+//! This module contains the implementation of a portal `VPortal`.
+
+use super::{backend, DomBackend, VDiff, VNode, VRender};
+use cfg_if::cfg_if;
+use std::fmt;
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        use stdweb::web::Element;
+    } else if #[cfg(feature = "web_sys")] {
+        use web_sys::Element;
+    }
+}
+
+/// A virtual node which renders its content into a `host` element elsewhere in the document,
+/// e.g. to escape an `overflow: hidden` ancestor.
+///
+/// Components inside of a portal still belong to the component which renders the portal:
+/// they receive its properties and are destroyed with it.
+#[derive(Clone)]
+pub struct VPortal {
+    /// The element the content is rendered into.
+    pub host: Element,
+    /// The content of the portal.
+    pub node: Box<VNode>,
+}
+
+impl VPortal {
+    /// Creates a portal which renders `node` into `host`.
+    ///
+    /// ```ignore
+    /// html! {
+    ///     <div class="overflow-hidden">
+    ///         { VPortal::new(modal_host, html! { <div class="modal">{ "Hello" }</div> }) }
+    ///     </div>
+    /// }
+    /// ```
+    pub fn new(host: Element, node: VNode) -> Self {
+        VPortal {
+            host,
+            node: Box::new(node),
+        }
+    }
+}
+
+impl VDiff for VPortal {
+    /// Remove the content of the portal from its host.
+    fn detach<B: DomBackend>(&mut self, backend: &B, _parent: &B::Element) -> Option<B::Node> {
+        if let Some(host) = backend::browser_element(backend, &self.host) {
+            self.node.detach(backend, &host);
+        }
+        // The portal doesn't take up any space in the parent
+        None
+    }
+
+    /// Renders the content into the host and leaves the parent untouched, so the previous
+    /// sibling is returned as the last node of the portal.
+    fn apply<B: DomBackend>(
+        &mut self,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
+        ancestor: Option<VNode>,
+    ) -> Option<B::Node> {
+        let ancestor = match ancestor {
+            Some(VNode::VPortal(mut vportal)) => {
+                if self.host == vportal.host {
+                    Some(*vportal.node)
+                } else {
+                    vportal.detach(backend, parent);
+                    None
+                }
+            }
+            Some(mut vnode) => {
+                vnode.detach(backend, parent);
+                None
+            }
+            None => None,
+        };
+        // The host is a part of the browser DOM, so other backends leave the content out
+        if let Some(host) = backend::browser_element(backend, &self.host) {
+            self.node.apply(backend, &host, None, ancestor);
+        }
+        previous_sibling.cloned()
+    }
+}
+
+impl VRender for VPortal {
+    /// The content is rendered on the client only, because the host is not a part of the
+    /// rendered markup.
+    fn render_html(&self, _html: &mut String) {}
+}
+
+impl From<VPortal> for VNode {
+    fn from(vportal: VPortal) -> Self {
+        VNode::VPortal(vportal)
+    }
+}
+
+impl fmt::Debug for VPortal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VPortal {{ node: {:?} }}", self.node)
+    }
+}
+
+impl PartialEq for VPortal {
+    fn eq(&self, other: &VPortal) -> bool {
+        self.host == other.host && self.node == other.node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html;
+    use crate::utils::document;
+    use crate::virtual_dom::Browser;
+    #[cfg(feature = "std_web")]
+    use stdweb::web::INode;
+
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[test]
+    fn renders_into_host() {
+        let parent = document().create_element("div").unwrap();
+        let host = document().create_element("div").unwrap();
+
+        let mut ancestor = html! {
+            <>
+                <span>{ "parent" }</span>
+                { VPortal::new(host.clone(), html! { <p>{ "first" }</p> }) }
+            </>
+        };
+        ancestor.apply(&Browser, &parent, None, None);
+        assert_eq!(parent.text_content().unwrap(), "parent");
+        assert_eq!(host.text_content().unwrap(), "first");
+
+        let mut vnode = html! {
+            <>
+                <span>{ "parent" }</span>
+                { VPortal::new(host.clone(), html! { <p>{ "second" }</p> }) }
+            </>
+        };
+        vnode.apply(&Browser, &parent, None, Some(ancestor));
+        assert_eq!(parent.text_content().unwrap(), "parent");
+        assert_eq!(host.text_content().unwrap(), "second");
+
+        vnode.detach(&Browser, &parent);
+        assert_eq!(parent.text_content().unwrap(), "");
+        assert_eq!(host.text_content().unwrap(), "");
+    }
+
+    #[test]
+    fn memory_dom_leaves_out_browser_nodes() {
+        use crate::virtual_dom::{MemoryDom, VExternal};
+
+        let parent = MemoryDom.create_element("div", None);
+        let host = document().create_element("div").unwrap();
+        let node = document().create_element("i").unwrap();
+        let view = || {
+            let node = cfg_match::cfg_match! {
+                feature = "std_web" => node.as_node().clone(),
+                feature = "web_sys" => node.clone().into(),
+            };
+            html! {
+                <>
+                    { VPortal::new(host.clone(), html! { <p>{ "portal" }</p> }) }
+                    { VNode::VRef(node) }
+                    { VExternal::new("editor", || panic!("only created in the browser")) }
+                    <span>{ "memory" }</span>
+                </>
+            }
+        };
+
+        let mut ancestor = view();
+        ancestor.apply(&MemoryDom, &parent, None, None);
+        let mut vnode = view();
+        vnode.apply(&MemoryDom, &parent, None, Some(ancestor));
+        assert_eq!(parent.children().len(), 1);
+        assert_eq!(parent.text_content(), "memory");
+        assert_eq!(host.text_content().unwrap(), "");
+
+        vnode.detach(&MemoryDom, &parent);
+        assert!(parent.children().is_empty());
+    }
+}
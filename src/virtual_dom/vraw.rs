@@ -0,0 +1,613 @@
+//! This module contains the implementation of a virtual node for raw HTML `VRaw`.
+
+use super::vtag::VOID_ELEMENTS;
+use super::{escape_attribute, Browser, DomBackend, Handle, Reform, VDiff, VNode, VRender};
+use cfg_if::cfg_if;
+use log::warn;
+use std::collections::HashSet;
+use std::fmt;
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        use stdweb::web::Node;
+    } else if #[cfg(feature = "web_sys")] {
+        use web_sys::Node;
+    }
+}
+
+/// Tags which are dropped together with their content by the sanitizer.
+const DROPPED_WITH_CONTENT: &[&str] = &["script", "style"];
+
+/// Attributes which contain URLs, so their scheme is checked by the sanitizer.
+const URL_ATTRIBUTES: &[&str] = &["href", "src"];
+
+/// Schemes which are allowed in URLs by the sanitizer.
+const SAFE_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// A virtual node which renders an HTML string in place.
+///
+/// Use `Html::from_html_unchecked` for trusted markup and `Html::from_html_sanitized` for
+/// markup which comes from users.
+pub struct VRaw {
+    /// The markup of the node.
+    pub html: String,
+    /// The nodes the markup was parsed into.
+    nodes: Vec<Handle<Node>>,
+}
+
+impl VRaw {
+    /// Creates a node which renders `html` as is.
+    pub fn new(html: String) -> Self {
+        VRaw {
+            html,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Returns the DOM nodes the markup was parsed into.
+    pub(crate) fn nodes(&self) -> &[Handle<Node>] {
+        &self.nodes
+    }
+
+    /// Returns the number of top level nodes the markup is parsed into by the browser.
+    pub(crate) fn node_count(&self) -> usize {
+        Browser.parse_html(&self.html).len()
+    }
+}
+
+impl Clone for VRaw {
+    fn clone(&self) -> Self {
+        VRaw::new(self.html.clone())
+    }
+}
+
+impl VDiff for VRaw {
+    /// Remove the parsed nodes from parent.
+    fn detach<B: DomBackend>(&mut self, backend: &B, parent: &B::Element) -> Option<B::Node> {
+        let next_sibling = self
+            .nodes
+            .last()
+            .and_then(|node| backend.next_sibling(backend.handle_node(node)));
+        for node in self.nodes.drain(..) {
+            if !backend.remove_child(parent, backend.handle_node(&node)) {
+                warn!("Node not found to remove VRaw");
+            }
+        }
+        next_sibling
+    }
+
+    /// Keeps the nodes of the ancestor if the markup didn't change, otherwise parses the
+    /// markup again.
+    fn apply<B: DomBackend>(
+        &mut self,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
+        ancestor: Option<VNode>,
+    ) -> Option<B::Node> {
+        let reform = match ancestor {
+            Some(VNode::VRaw(mut vraw)) => {
+                if self.html == vraw.html {
+                    self.nodes = vraw.nodes.split_off(0);
+                    Reform::Keep
+                } else {
+                    Reform::Before(vraw.detach(backend, parent))
+                }
+            }
+            Some(mut vnode) => Reform::Before(vnode.detach(backend, parent)),
+            None => Reform::Before(None),
+        };
+
+        if let Reform::Before(next_sibling) = reform {
+            let next_sibling =
+                next_sibling.or_else(|| previous_sibling.and_then(|p| backend.next_sibling(p)));
+            for node in backend.parse_html(&self.html) {
+                backend.insert_before(parent, &node, next_sibling.as_ref());
+                self.nodes.push(backend.node_handle(node));
+            }
+        }
+
+        // Empty markup doesn't take up any space
+        self.nodes
+            .last()
+            .map(|node| backend.handle_node(node).clone())
+            .or_else(|| previous_sibling.cloned())
+    }
+}
+
+impl VRender for VRaw {
+    fn render_html(&self, html: &mut String) {
+        html.push_str(&self.html);
+    }
+}
+
+impl From<VRaw> for VNode {
+    fn from(vraw: VRaw) -> Self {
+        VNode::VRaw(vraw)
+    }
+}
+
+impl fmt::Debug for VRaw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VRaw {{ html: {} }}", self.html)
+    }
+}
+
+impl PartialEq for VRaw {
+    fn eq(&self, other: &VRaw) -> bool {
+        self.html == other.html
+    }
+}
+
+/// The tags and attributes which are kept when HTML gets sanitized. Everything else is
+/// removed: disallowed tags are dropped but their text is kept, except for `script` and
+/// `style` which are dropped with their content. Event handler attributes like `onclick`
+/// and URLs with schemes other than `http`, `https` and `mailto` are always removed.
+#[derive(Clone, Debug)]
+pub struct Allowlist {
+    /// Allowed tag names in lowercase.
+    pub tags: HashSet<String>,
+    /// Allowed attribute names in lowercase.
+    pub attributes: HashSet<String>,
+}
+
+impl Default for Allowlist {
+    fn default() -> Self {
+        let tags = &[
+            "a",
+            "abbr",
+            "b",
+            "blockquote",
+            "br",
+            "code",
+            "del",
+            "div",
+            "em",
+            "h1",
+            "h2",
+            "h3",
+            "h4",
+            "h5",
+            "h6",
+            "hr",
+            "i",
+            "img",
+            "li",
+            "ol",
+            "p",
+            "pre",
+            "s",
+            "span",
+            "strong",
+            "sub",
+            "sup",
+            "table",
+            "tbody",
+            "td",
+            "th",
+            "thead",
+            "tr",
+            "u",
+            "ul",
+        ];
+        let attributes = &["alt", "class", "colspan", "href", "rowspan", "src", "title"];
+        Allowlist {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            attributes: attributes.iter().map(|attr| attr.to_string()).collect(),
+        }
+    }
+}
+
+impl Allowlist {
+    /// Removes everything from `input` which is not allowed. The output is well formed:
+    /// entities are escaped and all the tags are closed.
+    pub fn sanitize(&self, input: &str) -> String {
+        let mut html = String::with_capacity(input.len());
+        let mut open_tags: Vec<String> = Vec::new();
+        let mut rest = input;
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("<!--") {
+                rest = rest[4..].find("-->").map_or("", |end| &rest[4 + end + 3..]);
+            } else if c == '<' {
+                match Tag::parse(rest) {
+                    Some((tag, remaining)) => {
+                        rest = remaining;
+                        if !tag.closing && DROPPED_WITH_CONTENT.contains(&tag.name.as_str()) {
+                            let end = format!("</{}", tag.name);
+                            rest = rest
+                                .to_ascii_lowercase()
+                                .find(&end)
+                                .map_or("", |end| &rest[end..]);
+                        } else if self.tags.contains(&tag.name) {
+                            self.push_tag(tag, &mut open_tags, &mut html);
+                        }
+                    }
+                    None => {
+                        html.push_str("&lt;");
+                        rest = &rest[1..];
+                    }
+                }
+            } else if c == '&' {
+                let len = entity_len(rest).unwrap_or(0);
+                if len > 0 {
+                    html.push_str(&rest[..len]);
+                    rest = &rest[len..];
+                } else {
+                    html.push_str("&amp;");
+                    rest = &rest[1..];
+                }
+            } else if c == '>' {
+                html.push_str("&gt;");
+                rest = &rest[1..];
+            } else {
+                html.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        for tag in open_tags.iter().rev() {
+            html.push_str(&format!("</{}>", tag));
+        }
+        html
+    }
+
+    fn push_tag(&self, tag: Tag, open_tags: &mut Vec<String>, html: &mut String) {
+        if tag.closing {
+            if let Some(position) = open_tags.iter().rposition(|name| name == &tag.name) {
+                for name in open_tags.drain(position..).rev() {
+                    html.push_str(&format!("</{}>", name));
+                }
+            }
+            return;
+        }
+
+        html.push('<');
+        html.push_str(&tag.name);
+        for (name, value) in tag.attributes {
+            let allowed = self.attributes.contains(&name)
+                && !name.starts_with("on")
+                && (!URL_ATTRIBUTES.contains(&name.as_str()) || is_safe_url(&value));
+            if allowed {
+                html.push(' ');
+                html.push_str(&name);
+                html.push_str("=\"");
+                escape_attribute(&value, html);
+                html.push('"');
+            }
+        }
+        html.push('>');
+        if !VOID_ELEMENTS.contains(&tag.name.as_str()) {
+            open_tags.push(tag.name);
+        }
+    }
+}
+
+/// A tag parsed by the sanitizer.
+struct Tag {
+    /// The name in lowercase.
+    name: String,
+    closing: bool,
+    /// Attribute names in lowercase with decoded values.
+    attributes: Vec<(String, String)>,
+}
+
+impl Tag {
+    /// Parses a tag at the start of `input` and returns it with the rest of the input.
+    /// Returns `None` if `input` doesn't start with a complete tag.
+    fn parse(input: &str) -> Option<(Tag, &str)> {
+        let mut rest = &input[1..];
+        let closing = rest.starts_with('/');
+        if closing {
+            rest = &rest[1..];
+        }
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+            .unwrap_or(rest.len());
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = &rest[name_len..];
+
+        let mut attributes = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                return None;
+            } else if rest.starts_with('>') {
+                rest = &rest[1..];
+                break;
+            } else if rest.starts_with('/') || rest.starts_with('=') {
+                rest = &rest[1..];
+                continue;
+            }
+
+            let name_len = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+                .unwrap_or(rest.len());
+            let name = rest[..name_len].to_ascii_lowercase();
+            rest = rest[name_len..].trim_start();
+
+            let mut value = "";
+            if rest.starts_with('=') {
+                rest = rest[1..].trim_start();
+                if rest.starts_with('"') || rest.starts_with('\'') {
+                    let quote = &rest[..1];
+                    let end = rest[1..].find(quote)?;
+                    value = &rest[1..=end];
+                    rest = &rest[end + 2..];
+                } else {
+                    let len = rest
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(rest.len());
+                    value = &rest[..len];
+                    rest = &rest[len..];
+                }
+            }
+            attributes.push((name, decode_entities(value)));
+        }
+
+        let tag = Tag {
+            name,
+            closing,
+            attributes,
+        };
+        Some((tag, rest))
+    }
+}
+
+/// Parses `markup` into detached nodes of `backend` without a browser.
+///
+/// Unlike a browser it doesn't fix up misnested tags: a closing tag closes every element
+/// which was opened after the matching one and unmatched closing tags are ignored.
+pub(crate) fn parse_markup<B: DomBackend>(backend: &B, markup: &str) -> Vec<B::Node> {
+    let mut nodes = Vec::new();
+    let mut open_elements: Vec<(String, B::Element)> = Vec::new();
+    let mut text = String::new();
+    let append = |open_elements: &[(String, B::Element)], nodes: &mut Vec<B::Node>, node| {
+        match open_elements.last() {
+            Some((_, parent)) => backend.insert_before(parent, &node, None),
+            None => nodes.push(node),
+        }
+    };
+    let flush_text = |text: &mut String, open_elements: &[_], nodes: &mut Vec<_>| {
+        if !text.is_empty() {
+            let node = backend.text_node(&backend.create_text(&decode_entities(text)));
+            append(open_elements, nodes, node);
+            text.clear();
+        }
+    };
+
+    let mut rest = markup;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("<!--") {
+            flush_text(&mut text, &open_elements, &mut nodes);
+            rest = rest[4..].find("-->").map_or("", |end| &rest[4 + end + 3..]);
+            continue;
+        }
+        let tag = if c == '<' { Tag::parse(rest) } else { None };
+        let (tag, remaining) = match tag {
+            Some(parsed) => parsed,
+            None => {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+        };
+        flush_text(&mut text, &open_elements, &mut nodes);
+        rest = remaining;
+        if tag.closing {
+            if let Some(position) = open_elements
+                .iter()
+                .rposition(|(name, _)| *name == tag.name)
+            {
+                open_elements.truncate(position);
+            }
+            continue;
+        }
+
+        let element = backend.create_element(&tag.name, None);
+        for (name, value) in &tag.attributes {
+            backend.set_attribute(&element, name, value);
+        }
+        append(&open_elements, &mut nodes, backend.element_node(&element));
+        if DROPPED_WITH_CONTENT.contains(&tag.name.as_str()) {
+            // The content of scripts and styles isn't markup
+            let end = rest
+                .to_ascii_lowercase()
+                .find(&format!("</{}", tag.name))
+                .unwrap_or(rest.len());
+            if end > 0 {
+                let content = backend.create_text(&rest[..end]);
+                backend.insert_before(&element, &backend.text_node(&content), None);
+            }
+            rest = &rest[end..];
+        }
+        if !VOID_ELEMENTS.contains(&tag.name.as_str()) {
+            open_elements.push((tag.name, element));
+        }
+    }
+    flush_text(&mut text, &open_elements, &mut nodes);
+    nodes
+}
+
+/// Returns the length of the character reference at the start of `input`, e.g. `&amp;`.
+fn entity_len(input: &str) -> Option<usize> {
+    let body = &input[1..];
+    let offset = if body.starts_with("#x") || body.starts_with("#X") {
+        2
+    } else if body.starts_with('#') {
+        1
+    } else {
+        0
+    };
+    let digits = &body[offset..];
+    let len = digits
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(digits.len());
+    if len > 0 && digits[len..].starts_with(';') {
+        Some(1 + offset + len + 1)
+    } else {
+        None
+    }
+}
+
+/// Decodes numeric character references and the named ones which matter for URLs.
+fn decode_entities(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let len = entity_len(rest).unwrap_or(0);
+        let entity = &rest[1..len.max(2) - 1];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "colon" => Some(':'),
+            "tab" => Some('\t'),
+            "newline" => Some('\n'),
+            _ if len > 0 && entity.starts_with('#') => {
+                let code = if entity.starts_with("#x") || entity.starts_with("#X") {
+                    u32::from_str_radix(&entity[2..], 16).ok()
+                } else {
+                    entity[1..].parse().ok()
+                };
+                code.and_then(std::char::from_u32)
+            }
+            _ => None,
+        };
+        match c {
+            Some(c) if len > 0 => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Returns true if the URL is relative or has a safe scheme.
+fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters in schemes
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    match url.find(':') {
+        Some(end) => {
+            let scheme = &url[..end];
+            // A colon after the path, the query or the fragment started doesn't end a scheme
+            scheme.contains(&['/', '?', '#'][..])
+                || SAFE_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str())
+        }
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(input: &str) -> String {
+        Allowlist::default().sanitize(input)
+    }
+
+    #[test]
+    fn memory_dom_parses_markup() {
+        use crate::virtual_dom::{MemoryDom, MemoryNode};
+
+        let parent = MemoryDom.create_element("div", None);
+        let markup = r#"<p class="intro">1 &lt; 2<br><!-- note --><b>bold</i></b></p>tail"#;
+        let mut ancestor = VNode::from(VRaw::new(markup.into()));
+        ancestor.apply(&MemoryDom, &parent, None, None);
+
+        let children = parent.children();
+        assert_eq!(children.len(), 2);
+        let p = &children[0];
+        assert_eq!(p.tag().as_deref(), Some("p"));
+        assert_eq!(p.attribute("class").as_deref(), Some("intro"));
+        let tags: Vec<Option<String>> = p.children().iter().map(MemoryNode::tag).collect();
+        assert_eq!(tags, vec![None, Some("br".into()), Some("b".into())]);
+        assert_eq!(parent.text_content(), "1 < 2boldtail");
+
+        // The nodes are kept as long as the markup stays the same
+        let mut vnode = VNode::from(VRaw::new(markup.into()));
+        vnode.apply(&MemoryDom, &parent, None, Some(ancestor));
+        assert_eq!(parent.children(), children);
+
+        let mut replaced = VNode::from(VRaw::new("<i>new</i>".into()));
+        replaced.apply(&MemoryDom, &parent, None, Some(vnode));
+        assert_eq!(parent.children().len(), 1);
+        assert_eq!(parent.text_content(), "new");
+    }
+
+    #[test]
+    fn keeps_allowed_markup() {
+        let html = r#"<p class="intro">Hello <b>world</b> &amp; <a href="https://yew.rs">friends</a><br/></p>"#;
+        assert_eq!(
+            sanitize(html),
+            r#"<p class="intro">Hello <b>world</b> &amp; <a href="https://yew.rs">friends</a><br></p>"#
+        );
+    }
+
+    #[test]
+    fn removes_scripts_and_handlers() {
+        assert_eq!(sanitize("<script>alert(1)</script>text"), "text");
+        assert_eq!(sanitize("<SCRIPT>alert(1)</SCRIPT >text"), "text");
+        assert_eq!(sanitize("<img src=x onerror=alert(1)>"), r#"<img src="x">"#);
+        assert_eq!(
+            sanitize(r#"<p onclick="alert(1)" style="color: red">text</p>"#),
+            "<p>text</p>"
+        );
+        assert_eq!(sanitize("<iframe>frame</iframe>"), "frame");
+        assert_eq!(sanitize("<!-- <script>alert(1)</script> -->text"), "text");
+    }
+
+    #[test]
+    fn removes_unsafe_urls() {
+        assert_eq!(
+            sanitize(r#"<a href="javascript:alert(1)">a</a>"#),
+            "<a>a</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href=" JavaScript:alert(1)">a</a>"#),
+            "<a>a</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href="javascript&colon;alert(1)">a</a>"#),
+            "<a>a</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href="jav&#x61;script:alert(1)">a</a>"#),
+            "<a>a</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href="/path?next=a:b">a</a>"#),
+            r#"<a href="/path?next=a:b">a</a>"#
+        );
+        assert_eq!(
+            sanitize(r#"<a href="?a=1&amp;b=2">a</a>"#),
+            r#"<a href="?a=1&amp;b=2">a</a>"#
+        );
+    }
+
+    #[test]
+    fn escapes_malformed_markup() {
+        assert_eq!(sanitize("1 < 2 && 3 > 2"), "1 &lt; 2 &amp;&amp; 3 &gt; 2");
+        assert_eq!(sanitize("<b>bold"), "<b>bold</b>");
+        assert_eq!(sanitize("<b><i>text</b>"), "<b><i>text</i></b>");
+        assert_eq!(sanitize("</i>text"), "text");
+        assert_eq!(sanitize("<img src=\"x"), "&lt;img src=\"x");
+        assert_eq!(
+            sanitize("<a title='\"quoted\"'>"),
+            "<a title=\"&quot;quoted&quot;\"></a>"
+        );
+    }
+}
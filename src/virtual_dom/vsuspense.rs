@@ -0,0 +1,250 @@
+//! This module contains the implementation of a suspense boundary `VSuspense`.
+
+use super::{backend, DomBackend, Handle, VDiff, VNode, VRender};
+use cfg_if::cfg_if;
+use log::warn;
+use std::fmt;
+cfg_if! {
+    if #[cfg(feature = "std_web")] {
+        use stdweb::web::{Element, Node};
+    } else if #[cfg(feature = "web_sys")] {
+        use web_sys::{Element, Node};
+    }
+}
+
+/// A virtual node which renders `fallback` in place of its children while they are
+/// suspended.
+///
+/// Suspended children stay mounted in a detached element, so they keep their state and the
+/// same DOM nodes are moved back once they are resumed. The content is rendered after an
+/// empty text node, which keeps the place of the node while the content is swapped. It's
+/// rendered by the [`Suspense`](../../components/suspense/struct.Suspense.html) component.
+#[derive(Clone)]
+pub struct VSuspense {
+    children: Box<VNode>,
+    fallback: Box<VNode>,
+    suspended: bool,
+    /// The element which keeps the children while they are suspended.
+    detached_parent: Option<Handle<Element>>,
+    /// The empty text node which precedes the content.
+    anchor: Option<Handle<Node>>,
+}
+
+impl VSuspense {
+    /// Creates a node which renders `children`, or `fallback` if `suspended` is set.
+    pub fn new(children: VNode, fallback: VNode, suspended: bool) -> Self {
+        VSuspense {
+            children: Box::new(children),
+            fallback: Box::new(fallback),
+            suspended,
+            detached_parent: None,
+            anchor: None,
+        }
+    }
+
+    /// Returns the content which is rendered in place of the node.
+    pub(crate) fn content(&self) -> &VNode {
+        if self.suspended {
+            &self.fallback
+        } else {
+            &self.children
+        }
+    }
+
+    /// Returns the content which is rendered in place of the node, see `content`.
+    pub(crate) fn content_mut(&mut self) -> &mut VNode {
+        if self.suspended {
+            &mut self.fallback
+        } else {
+            &mut self.children
+        }
+    }
+
+    /// Returns the top level DOM nodes of the node in document order, starting with the
+    /// anchor.
+    pub(crate) fn dom_nodes(&self) -> Vec<Handle<Node>> {
+        let mut nodes: Vec<Handle<Node>> = self.anchor.iter().cloned().collect();
+        nodes.extend(self.content().dom_nodes());
+        nodes
+    }
+
+    /// Moves the anchor and the content in front of `next_sibling` in `next_parent`.
+    pub(crate) fn shift<B: DomBackend>(
+        &self,
+        backend: &B,
+        next_parent: &B::Element,
+        next_sibling: Option<&B::Node>,
+    ) {
+        if let Some(anchor) = &self.anchor {
+            backend.insert_before(next_parent, backend.handle_node(anchor), next_sibling);
+        }
+        self.content().shift(backend, next_parent, next_sibling);
+    }
+
+    fn detached_parent<B: DomBackend>(&mut self, backend: &B) -> B::Element {
+        let handle = self
+            .detached_parent
+            .get_or_insert_with(|| backend.element_handle(backend.create_element("div", None)));
+        backend.handle_element(handle).clone()
+    }
+}
+
+impl VDiff for VSuspense {
+    /// Remove the anchor, the children and the fallback.
+    fn detach<B: DomBackend>(&mut self, backend: &B, parent: &B::Element) -> Option<B::Node> {
+        let next_sibling = if self.suspended {
+            let detached_parent = self.detached_parent(backend);
+            self.children.detach(backend, &detached_parent);
+            self.fallback.detach(backend, parent)
+        } else {
+            self.children.detach(backend, parent)
+        };
+        match self.anchor.take() {
+            Some(anchor) => {
+                let anchor = backend.handle_node(&anchor);
+                let anchor_sibling = backend.next_sibling(anchor);
+                if !backend.remove_child(parent, anchor) {
+                    warn!("Node not found to remove VSuspense");
+                }
+                next_sibling.or(anchor_sibling)
+            }
+            None => next_sibling,
+        }
+    }
+
+    /// Diffs the children against the children of the ancestor, wherever they are, and moves
+    /// them in or out of the parent when the node gets suspended or resumed.
+    fn apply<B: DomBackend>(
+        &mut self,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
+        ancestor: Option<VNode>,
+    ) -> Option<B::Node> {
+        let (children_ancestor, fallback_ancestor) = match ancestor {
+            Some(VNode::VSuspense(mut vsuspense)) => {
+                self.detached_parent = vsuspense.detached_parent.take();
+                self.anchor = vsuspense.anchor.take();
+                let children = *vsuspense.children;
+                let mut fallback = *vsuspense.fallback;
+                match (vsuspense.suspended, self.suspended) {
+                    (false, true) => {
+                        let detached_parent = self.detached_parent(backend);
+                        children.shift(backend, &detached_parent, None);
+                        (Some(children), None)
+                    }
+                    (true, false) => {
+                        let next_sibling = fallback.detach(backend, parent);
+                        children.shift(backend, parent, next_sibling.as_ref());
+                        (Some(children), None)
+                    }
+                    (true, true) => (Some(children), Some(fallback)),
+                    (false, false) => (Some(children), None),
+                }
+            }
+            ancestor if self.suspended => (None, ancestor),
+            ancestor => (ancestor, None),
+        };
+
+        let anchor = match &self.anchor {
+            Some(anchor) => backend.handle_node(anchor).clone(),
+            None => {
+                // The anchor goes in front of the nodes the content diffs against
+                let next_sibling = children_ancestor
+                    .iter()
+                    .chain(fallback_ancestor.iter())
+                    .flat_map(VNode::dom_nodes)
+                    .find_map(|node| backend::owned_node(backend, &node))
+                    .or_else(|| previous_sibling.and_then(|node| backend.next_sibling(node)));
+                let anchor = backend.text_node(&backend.create_text(""));
+                backend.insert_before(parent, &anchor, next_sibling.as_ref());
+                self.anchor = Some(backend.node_handle(anchor.clone()));
+                anchor
+            }
+        };
+        let last_node = if self.suspended {
+            let detached_parent = self.detached_parent(backend);
+            self.children
+                .apply(backend, &detached_parent, None, children_ancestor);
+            self.fallback
+                .apply(backend, parent, Some(&anchor), fallback_ancestor)
+        } else {
+            self.children
+                .apply(backend, parent, Some(&anchor), children_ancestor)
+        };
+        last_node.or(Some(anchor))
+    }
+}
+
+impl VRender for VSuspense {
+    fn render_html(&self, html: &mut String) {
+        self.content().render_html(html);
+    }
+}
+
+impl From<VSuspense> for VNode {
+    fn from(vsuspense: VSuspense) -> Self {
+        VNode::VSuspense(vsuspense)
+    }
+}
+
+impl fmt::Debug for VSuspense {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "VSuspense {{ children: {:?}, fallback: {:?}, suspended: {} }}",
+            self.children, self.fallback, self.suspended
+        )
+    }
+}
+
+impl PartialEq for VSuspense {
+    fn eq(&self, other: &VSuspense) -> bool {
+        self.suspended == other.suspended
+            && self.children == other.children
+            && self.fallback == other.fallback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html;
+    use crate::virtual_dom::{MemoryDom, VRaw};
+    #[cfg(feature = "wasm_test")]
+    use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
+
+    #[cfg(feature = "wasm_test")]
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[test]
+    fn keeps_place_of_children_without_nodes() {
+        let parent = MemoryDom.create_element("ul", None);
+        let view = |suspended| {
+            let children = VRaw::new(String::new()).into();
+            let fallback = html! { <li>{ "fallback" }</li> };
+            html! {
+                <>
+                    { VSuspense::new(children, fallback, suspended) }
+                    <li>{ "after" }</li>
+                </>
+            }
+        };
+
+        let mut ancestor = view(false);
+        ancestor.apply(&MemoryDom, &parent, None, None);
+        assert_eq!(parent.text_content(), "after");
+
+        let mut vnode = view(true);
+        vnode.apply(&MemoryDom, &parent, None, Some(ancestor));
+        assert_eq!(parent.text_content(), "fallbackafter");
+
+        let ancestor = vnode;
+        let mut vnode = view(false);
+        vnode.apply(&MemoryDom, &parent, None, Some(ancestor));
+        assert_eq!(parent.text_content(), "after");
+
+        vnode.detach(&MemoryDom, &parent);
+        assert!(parent.children().is_empty());
+    }
+}
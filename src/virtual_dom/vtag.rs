@@ -1,31 +1,29 @@
 //! This module contains the implementation of a virtual element node `VTag`.
 
 use super::{
-    Attributes, Classes, Listener, Listeners, Patch, Reform, Transformer, VDiff, VList, VNode,
+    escape_attribute, escape_html, Attributes, Classes, DomBackend, Handle, Listener, Listeners,
+    Patch, Properties, PropertyValue, Reform, Styles, Transformer, VDiff, VList, VNode, VRender,
 };
-use crate::html::NodeRef;
-use crate::utils::document;
+use crate::html::{activate_listener, ListenerHandle, NodeRef};
 use cfg_if::cfg_if;
 use cfg_match::cfg_match;
 use log::warn;
 use std::borrow::Cow;
 use std::cmp::PartialEq;
 use std::fmt;
+use std::mem;
 use std::rc::Rc;
 cfg_if! {
     if #[cfg(feature = "std_web")] {
-        use crate::html::EventListener;
         #[allow(unused_imports)]
         use stdweb::{_js_impl, js};
-        use stdweb::unstable::TryFrom;
+        use stdweb::unstable::{TryFrom, TryInto};
         use stdweb::web::html_element::{InputElement, TextAreaElement};
-        use stdweb::web::{Element, IElement, INode, Node};
+        use stdweb::web::{Element, IElement, INode};
     } else if #[cfg(feature = "web_sys")] {
-        use gloo::events::EventListener;
-        use std::ops::Deref;
         use wasm_bindgen::JsCast;
         use web_sys::{
-            Element, HtmlInputElement as InputElement, HtmlTextAreaElement as TextAreaElement, Node,
+            Element, HtmlInputElement as InputElement, HtmlTextAreaElement as TextAreaElement,
         };
     }
 }
@@ -36,6 +34,24 @@ pub const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
 /// Default namespace for html elements
 pub const HTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
 
+/// Restores the value of `element` if it's controlled and the user changed it.
+///
+/// It's called after the listeners of the element handled an event, so the element keeps
+/// the user's input only if the component rendered it.
+pub(crate) fn restore_controlled<B: DomBackend>(backend: &B, element: &B::Element) {
+    if let Some(value) = backend.controlled_value(element) {
+        if backend.value(element).as_ref() != Some(&value) {
+            backend.set_value(element, &value);
+        }
+    }
+}
+
+/// Elements which can't have any children and must not have a closing tag.
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
 /// A type for a virtual
 /// [Element](https://developer.mozilla.org/en-US/docs/Web/API/Element)
 /// representation.
@@ -43,18 +59,31 @@ pub struct VTag {
     /// A tag of the element.
     tag: Cow<'static, str>,
     /// A reference to the `Element`.
-    pub reference: Option<Element>,
+    pub reference: Option<Handle<Element>>,
     /// List of attached listeners.
     pub listeners: Listeners,
     /// List of attributes.
     pub attributes: Attributes,
+    /// List of DOM properties, which are set on the element directly.
+    pub properties: Properties,
     /// List of children nodes
     pub children: VList,
     /// List of attached classes.
     pub classes: Classes,
+    /// List of inline styles.
+    pub styles: Styles,
     /// Contains a value of an
-    /// [InputElement](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input).
+    /// [InputElement](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input),
+    /// a `textarea` or a `select` element.
     pub value: Option<String>,
+    /// Contains the selected values of a
+    /// [multiple select](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/select#attr-multiple).
+    /// They take precedence over `value`.
+    pub values: Option<Vec<String>>,
+    /// Makes the `value` of an input or textarea controlled: the element always shows
+    /// `value`, it's compared with the live value on every diff and restored after the
+    /// listeners of the element handled an event.
+    pub controlled: bool,
     /// Contains
     /// [kind](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input#Form_%3Cinput%3E_types)
     /// value of an `InputElement`.
@@ -67,8 +96,13 @@ pub struct VTag {
     pub checked: bool,
     /// A node reference used for DOM access in Component lifecycle methods
     pub node_ref: NodeRef,
+    /// A key used to match this element against its ancestor in a list of children.
+    pub key: Option<String>,
     /// Keeps handler for attached listeners to have an opportunity to drop them later.
-    captured: Vec<EventListener>,
+    /// Handlers are stored in the same order as `listeners`.
+    captured: Vec<ListenerHandle>,
+    /// Set if the element was adopted from server-rendered markup.
+    hydrated: bool,
 }
 
 impl Clone for VTag {
@@ -78,13 +112,19 @@ impl Clone for VTag {
             reference: None,
             listeners: self.listeners.clone(),
             attributes: self.attributes.clone(),
+            properties: self.properties.clone(),
             children: self.children.clone(),
             classes: self.classes.clone(),
+            styles: self.styles.clone(),
             value: self.value.clone(),
+            values: self.values.clone(),
+            controlled: self.controlled,
             kind: self.kind.clone(),
             checked: self.checked,
             node_ref: self.node_ref.clone(),
+            key: self.key.clone(),
             captured: Vec::new(),
+            hydrated: false,
         }
     }
 }
@@ -96,17 +136,96 @@ impl VTag {
             tag: tag.into(),
             reference: None,
             classes: Classes::new(),
+            styles: Styles::new(),
             attributes: Attributes::new(),
+            properties: Properties::new(),
             listeners: Vec::new(),
             captured: Vec::new(),
             children: VList::new_without_placeholder(),
             node_ref: NodeRef::default(),
+            key: None,
             value: None,
+            values: None,
+            controlled: false,
             kind: None,
             // In HTML node `checked` attribute sets `defaultChecked` parameter,
             // but we use own field to control real `checked` parameter
             checked: false,
+            hydrated: false,
+        }
+    }
+
+    /// Creates a `VTag` which represents an existing `Element` and its children, so that
+    /// applying a `VTag` over it only patches the differences.
+    pub(crate) fn adopt(element: Element) -> Self {
+        let is_html =
+            element.namespace_uri().as_deref().unwrap_or(HTML_NAMESPACE) == HTML_NAMESPACE;
+        // HTML tag names are reported in uppercase
+        let tag = if is_html {
+            element.node_name().to_lowercase()
+        } else {
+            element.node_name()
+        };
+        let mut vtag = VTag::new(tag);
+
+        let names = cfg_match! {
+            feature = "std_web" => element.get_attribute_names(),
+            feature = "web_sys" => element
+                .get_attribute_names()
+                .iter()
+                .filter_map(|name| name.as_string())
+                .collect::<Vec<String>>(),
+        };
+        for name in names {
+            let value = element.get_attribute(&name).unwrap_or_default();
+            match name.as_str() {
+                "class" => vtag.set_classes(value),
+                "style" => vtag.set_styles(value),
+                // Mirrors the attributes which `html!` treats specially
+                "type" => vtag.kind = Some(value),
+                "checked" => vtag.checked = true,
+                "value" if vtag.tag == "input" || vtag.tag == "textarea" => {}
+                _ => {
+                    vtag.attributes.insert(name, value);
+                }
+            }
+        }
+
+        if let Some(input) = {
+            cfg_match! {
+                feature = "std_web" => InputElement::try_from(element.clone()).ok(),
+                feature = "web_sys" => element.dyn_ref::<InputElement>(),
+            }
+        } {
+            vtag.value = Some(cfg_match! {
+                feature = "std_web" => input.raw_value(),
+                feature = "web_sys" => input.value(),
+            });
+            vtag.checked = cfg_match! {
+                feature = "std_web" => js!( return @{input}.checked; ).try_into().unwrap_or(false),
+                feature = "web_sys" => input.checked(),
+            };
+        } else if let Some(tae) = {
+            cfg_match! {
+                feature = "std_web" => TextAreaElement::try_from(element.clone()).ok(),
+                feature = "web_sys" => element.dyn_ref::<TextAreaElement>(),
+            }
+        } {
+            vtag.value = Some(tae.value());
         }
+
+        // The content of a `textarea` is its value
+        if vtag.tag != "textarea" {
+            vtag.children = VList::adopt_children(&element);
+        }
+        vtag.reference = Some(Handle::Browser(element));
+        vtag.hydrated = true;
+        vtag
+    }
+
+    /// Returns true if the element was adopted from server-rendered markup.
+    pub(crate) fn is_hydrated(&self) -> bool {
+        self.hydrated
     }
 
     /// Returns tag of an `Element`. In HTML tags are always uppercase.
@@ -149,12 +268,39 @@ impl VTag {
         self.classes = classes.into();
     }
 
+    /// Adds a single inline style property to this virtual node. Actually it will set by
+    /// [CSSStyleDeclaration.setProperty](https://developer.mozilla.org/en-US/docs/Web/API/CSSStyleDeclaration/setProperty)
+    /// call later.
+    pub fn add_style(&mut self, property: &str, value: &str) {
+        self.styles.push(property, value);
+    }
+
+    /// Sets inline styles of this virtual node. Only the properties which differ from the
+    /// ancestor are updated.
+    pub fn set_styles(&mut self, styles: impl Into<Styles>) {
+        self.styles = styles.into();
+    }
+
     /// Sets `value` for an
     /// [InputElement](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input).
     pub fn set_value<T: ToString>(&mut self, value: &T) {
         self.value = Some(value.to_string());
     }
 
+    /// Sets the selected values of a `select` element with the `multiple` attribute.
+    pub fn set_values<I, T>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: ToString,
+    {
+        self.values = Some(values.into_iter().map(|value| value.to_string()).collect());
+    }
+
+    /// Makes the `value` of an input or textarea controlled, see `VTag::controlled`.
+    pub fn set_controlled(&mut self, controlled: bool) {
+        self.controlled = controlled;
+    }
+
     /// Sets `kind` property of an
     /// [InputElement](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input).
     /// Same as set `type` attribute.
@@ -185,6 +331,13 @@ impl VTag {
         }
     }
 
+    /// Adds a DOM property to a virtual node. Unlike attributes, properties are set on the
+    /// element directly, so they can have any type, e.g. `indeterminate` of a checkbox or an
+    /// object passed to a custom element.
+    pub fn add_property<T: Into<PropertyValue>>(&mut self, name: &str, value: T) {
+        self.properties.insert(name.to_owned(), value.into());
+    }
+
     /// Adds new listener to the node.
     /// It's boxed because we want to keep it in a single list.
     /// Later `Listener::attach` will attach an actual listener to a DOM node.
@@ -201,20 +354,54 @@ impl VTag {
         }
     }
 
-    /// If there is no ancestor or the classes, or the order, differs from the ancestor:
-    /// - Returns the classes of self separated by spaces.
+    /// Compares the classes with the classes of the ancestor:
+    /// - Returns `Patch::Add` for the classes the ancestor doesn't have.
+    /// - Returns `Patch::Remove` for the classes only the ancestor has.
     ///
-    /// Otherwise None is returned.
-    fn diff_classes<'a>(&'a self, ancestor: &'a Option<Box<Self>>) -> Option<String> {
-        if ancestor
-            .as_ref()
-            .map(|ancestor| self.classes.ne(&ancestor.classes))
-            .unwrap_or(true)
-        {
-            Some(self.classes.to_string())
-        } else {
-            None
-        }
+    /// The order of the classes is ignored, so classes which were added to the element
+    /// outside of yew are preserved.
+    fn diff_classes<'a>(
+        &'a self,
+        ancestor: &'a Self,
+    ) -> impl Iterator<Item = Patch<&'a str, ()>> + 'a {
+        let to_add = self
+            .classes
+            .iter()
+            .filter(move |class| !ancestor.classes.contains(class))
+            .map(|class| Patch::Add(class, ()));
+        let to_remove = ancestor
+            .classes
+            .iter()
+            .filter(move |class| !self.classes.contains(class))
+            .map(Patch::Remove);
+
+        to_add.chain(to_remove)
+    }
+
+    /// Similar to `diff_attributes` except for inline styles.
+    fn diff_styles<'a>(
+        &'a self,
+        ancestor: &'a Option<Box<Self>>,
+    ) -> impl Iterator<Item = Patch<&'a str, &'a str>> + 'a {
+        let to_add_or_replace = self.styles.iter().filter_map(move |(property, value)| {
+            match ancestor
+                .as_ref()
+                .and_then(|ancestor| ancestor.styles.get(property))
+            {
+                None => Some(Patch::Add(property, value)),
+                Some(ancestor_value) if value != ancestor_value => {
+                    Some(Patch::Replace(property, value))
+                }
+                _ => None,
+            }
+        });
+        let to_remove = ancestor
+            .iter()
+            .flat_map(|ancestor| ancestor.styles.iter())
+            .filter(move |(property, _)| self.styles.get(property).is_none())
+            .map(|(property, _)| Patch::Remove(property));
+
+        to_add_or_replace.chain(to_remove)
     }
 
     /// Similar to diff_classes except for attributes.
@@ -248,6 +435,33 @@ impl VTag {
         to_add_or_replace.chain(to_remove)
     }
 
+    /// Similar to `diff_attributes` except for properties.
+    fn diff_properties<'a>(
+        &'a self,
+        ancestor: &'a Option<Box<Self>>,
+    ) -> impl Iterator<Item = Patch<&'a str, &'a PropertyValue>> + 'a {
+        let to_add_or_replace =
+            self.properties.iter().filter_map(move |(key, value)| {
+                match ancestor
+                    .as_ref()
+                    .and_then(|ancestor| ancestor.properties.get(&**key))
+                {
+                    None => Some(Patch::Add(&**key, value)),
+                    Some(ancestor_value) if value != ancestor_value => {
+                        Some(Patch::Replace(&**key, value))
+                    }
+                    _ => None,
+                }
+            });
+        let to_remove = ancestor
+            .iter()
+            .flat_map(|ancestor| ancestor.properties.keys())
+            .filter(move |key| !self.properties.contains_key(&**key))
+            .map(|key| Patch::Remove(&**key));
+
+        to_add_or_replace.chain(to_remove)
+    }
+
     /// Similar to `diff_attributers` except there is only a single `kind`.
     fn diff_kind<'a>(&'a self, ancestor: &'a Option<Box<Self>>) -> Option<Patch<&'a str, ()>> {
         match (
@@ -286,31 +500,91 @@ impl VTag {
         }
     }
 
-    fn apply_diffs(&mut self, ancestor: &Option<Box<Self>>) {
-        let element = self.reference.as_ref().expect("element expected");
+    /// Returns the values to select if this is a controlled `select` element.
+    fn selected_values(&self) -> Option<Vec<&str>> {
+        if self.tag != "select" {
+            return None;
+        }
+        match (&self.values, &self.value) {
+            (Some(values), _) => Some(values.iter().map(String::as_str).collect()),
+            (None, Some(value)) => Some(vec![value.as_str()]),
+            (None, None) => None,
+        }
+    }
 
+    /// Selects the options of a controlled `select` element.
+    ///
+    /// It has to be done after the children are rendered, because the options must exist,
+    /// and on every diff, because the user changes the selection too.
+    fn apply_selection<B: DomBackend>(&self, backend: &B, element: &B::Element) {
+        if let Some(values) = self.selected_values() {
+            backend.set_selected(element, &values);
+        }
+    }
+
+    /// Returns the value of an `option` element, which is its text if it has no `value`.
+    fn option_value(&self) -> String {
+        match self.attributes.get("value") {
+            Some(value) => value.clone(),
+            None => self
+                .children
+                .children
+                .iter()
+                .filter_map(|child| match child {
+                    VNode::VText(vtext) => Some(vtext.text.as_str()),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Patches `element` with the differences to the ancestor.
+    fn apply_diffs<B: DomBackend>(
+        &self,
+        backend: &B,
+        element: &B::Element,
+        ancestor: &Option<Box<Self>>,
+    ) {
         // Update parameters
-        let class_str = self.diff_classes(ancestor);
-        if let Some(class_str) = class_str {
-            element
-                .set_attribute("class", &class_str)
-                .expect("could not set class");
+        if let Some(ancestor) = ancestor {
+            for change in self.diff_classes(ancestor) {
+                match change {
+                    Patch::Add(class, _) | Patch::Replace(class, _) => {
+                        backend.add_class(element, class)
+                    }
+                    Patch::Remove(class) => backend.remove_class(element, class),
+                }
+            }
+        } else if !self.classes.is_empty() {
+            // A new element gets all of the classes at once
+            backend.set_attribute(element, "class", &self.classes.to_string());
         }
 
-        let changes = self.diff_attributes(ancestor);
-        for change in changes {
+        for change in self.diff_attributes(ancestor) {
             match change {
                 Patch::Add(key, value) | Patch::Replace(key, value) => {
-                    element
-                        .set_attribute(&key, &value)
-                        .expect("invalid attribute key");
+                    backend.set_attribute(element, key, value);
                 }
-                Patch::Remove(key) => {
-                    cfg_match! {
-                        feature = "std_web" => element.remove_attribute(&key),
-                        feature = "web_sys" => element.remove_attribute(&key).expect("could not remove class"),
-                    };
+                Patch::Remove(key) => backend.remove_attribute(element, key),
+            }
+        }
+
+        for change in self.diff_styles(ancestor) {
+            match change {
+                Patch::Add(property, value) | Patch::Replace(property, value) => {
+                    backend.set_style(element, property, value);
                 }
+                Patch::Remove(property) => backend.remove_style(element, property),
+            }
+        }
+
+        for change in self.diff_properties(ancestor) {
+            match change {
+                Patch::Add(key, value) | Patch::Replace(key, value) => {
+                    backend.set_property(element, key, value);
+                }
+                // Properties can't be removed, only reset
+                Patch::Remove(key) => backend.remove_property(element, key),
             }
         }
 
@@ -318,74 +592,61 @@ impl VTag {
         // I override behavior of attributes to make it more clear
         // and useful in templates. For example I interpret `checked`
         // attribute as `checked` parameter, not `defaultChecked` as browsers do
-        if let Some(input) = {
-            cfg_match! {
-                feature = "std_web" => InputElement::try_from(element.clone()).ok(),
-                feature = "web_sys" => element.dyn_ref::<InputElement>(),
-            }
-        } {
-            if let Some(change) = self.diff_kind(ancestor) {
-                let kind = match change {
-                    Patch::Add(kind, _) | Patch::Replace(kind, _) => kind,
-                    Patch::Remove(_) => "",
-                };
-                cfg_match! {
-                    feature = "std_web" => ({
-                        //https://github.com/koute/stdweb/commit/3b85c941db00b8e3c942624afd50c5929085fb08
-                        //input.set_kind(&kind);
-                        let input = &input;
-                        js! { @(no_return)
-                            @{input}.type = @{kind};
-                        }
-                    }),
-                    feature = "web_sys" => input.set_type(kind),
-                }
-            }
-
-            if let Some(change) = self.diff_value(ancestor) {
-                let raw_value = match change {
-                    Patch::Add(kind, _) | Patch::Replace(kind, _) => kind,
-                    Patch::Remove(_) => "",
-                };
-                cfg_match! {
-                    feature = "std_web" => input.set_raw_value(raw_value),
-                    feature = "web_sys" => input.set_value(raw_value),
-                };
-            }
+        if let Some(change) = self.diff_kind(ancestor) {
+            let kind = match change {
+                Patch::Add(kind, _) | Patch::Replace(kind, _) => kind,
+                Patch::Remove(_) => "",
+            };
+            backend.set_kind(element, kind);
+        }
 
-            // IMPORTANT! This parameter has to be set every time
-            // to prevent strange behaviour in the browser when the DOM changes
-            set_checked(&input, self.checked);
-        } else if let Some(tae) = {
-            cfg_match! {
-                feature = "std_web" => TextAreaElement::try_from(element.clone()).ok(),
-                feature = "web_sys" => element.dyn_ref::<TextAreaElement>(),
+        match self.value.as_ref().filter(|_| self.controlled) {
+            Some(value) => {
+                // The user could have changed the value since the last render
+                if backend.value(element).as_ref() != Some(value) {
+                    backend.set_value(element, value);
+                }
+                backend.set_controlled_value(element, Some(value));
             }
-        } {
-            if let Some(change) = self.diff_value(ancestor) {
-                let value = match change {
-                    Patch::Add(kind, _) | Patch::Replace(kind, _) => kind,
-                    Patch::Remove(_) => "",
-                };
-                tae.set_value(value);
+            None => {
+                if let Some(change) = self.diff_value(ancestor) {
+                    let value = match change {
+                        Patch::Add(value, _) | Patch::Replace(value, _) => value,
+                        Patch::Remove(_) => "",
+                    };
+                    backend.set_value(element, value);
+                }
+                if ancestor
+                    .as_ref()
+                    .filter(|ancestor| ancestor.controlled)
+                    .is_some()
+                {
+                    backend.set_controlled_value(element, None);
+                }
             }
         }
+
+        // IMPORTANT! This parameter has to be set every time
+        // to prevent strange behaviour in the browser when the DOM changes
+        backend.set_checked(element, self.checked);
     }
 }
 
 impl VDiff for VTag {
     /// Remove VTag from parent.
-    fn detach(&mut self, parent: &Element) -> Option<Node> {
-        let node = self
+    fn detach<B: DomBackend>(&mut self, backend: &B, parent: &B::Element) -> Option<B::Node> {
+        let element = self
             .reference
             .take()
             .expect("tried to remove not rendered VTag from DOM");
+        let element = backend.handle_element(&element);
 
         // recursively remove its children
-        self.children.detach(&node);
+        self.children.detach(backend, element);
 
-        let next_sibling = node.next_sibling();
-        if parent.remove_child(&node).is_err() {
+        let node = backend.element_node(element);
+        let next_sibling = backend.next_sibling(&node);
+        if !backend.remove_child(parent, &node) {
             warn!("Node not found to remove VTag");
         }
         next_sibling
@@ -393,12 +654,13 @@ impl VDiff for VTag {
 
     /// Renders virtual tag over DOM `Element`, but it also compares this with an ancestor `VTag`
     /// to compute what to patch in the actual DOM nodes.
-    fn apply(
+    fn apply<B: DomBackend>(
         &mut self,
-        parent: &Element,
-        previous_sibling: Option<&Node>,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
         ancestor: Option<VNode>,
-    ) -> Option<Node> {
+    ) -> Option<B::Node> {
         assert!(
             self.reference.is_none(),
             "reference is ignored so must not be set"
@@ -406,18 +668,31 @@ impl VDiff for VTag {
         let (reform, mut ancestor) = {
             match ancestor {
                 Some(VNode::VTag(mut vtag)) => {
-                    if self.tag == vtag.tag {
-                        // If tags are equal, preserve the reference that already exists.
+                    // Adopted elements never have a key
+                    if self.tag == vtag.tag && (self.key == vtag.key || vtag.hydrated) {
+                        // If tags and keys are equal, preserve the reference that already exists.
                         self.reference = vtag.reference.take();
                         (Reform::Keep, Some(vtag))
                     } else {
+                        if vtag.hydrated {
+                            warn!(
+                                "Hydration mismatch: expected <{}> but found <{}>",
+                                self.tag, vtag.tag
+                            );
+                        }
                         // We have to create a new reference, remove ancestor.
-                        (Reform::Before(vtag.detach(parent)), None)
+                        (Reform::Before(vtag.detach(backend, parent)), None)
                     }
                 }
                 Some(mut vnode) => {
+                    if vnode.is_hydrated() {
+                        warn!(
+                            "Hydration mismatch: expected {:?} but found {:?}",
+                            self, vnode
+                        );
+                    }
                     // It is not a VTag variant we must remove the ancestor.
-                    (Reform::Before(vnode.detach(parent)), None)
+                    (Reform::Before(vnode.detach(backend, parent)), None)
                 }
                 None => (Reform::Before(None), None),
             }
@@ -431,80 +706,176 @@ impl VDiff for VTag {
         match reform {
             Reform::Keep => {}
             Reform::Before(next_sibling) => {
-                let element = if self.tag == "svg"
-                    || parent
-                        .namespace_uri()
+                let namespace = if self.tag == "svg"
+                    || backend
+                        .namespace(parent)
                         .map_or(false, |ns| ns == SVG_NAMESPACE)
                 {
-                    let namespace = SVG_NAMESPACE;
-                    #[cfg(feature = "web_sys")]
-                    let namespace = Some(namespace);
-                    document()
-                        .create_element_ns(namespace, &self.tag)
-                        .expect("can't create namespaced element for vtag")
+                    Some(SVG_NAMESPACE)
                 } else {
-                    document()
-                        .create_element(&self.tag)
-                        .expect("can't create element for vtag")
+                    None
                 };
+                let element = backend.create_element(&self.tag, namespace);
+
+                let next_sibling =
+                    next_sibling.or_else(|| previous_sibling.and_then(|p| backend.next_sibling(p)));
+                backend.insert_before(
+                    parent,
+                    &backend.element_node(&element),
+                    next_sibling.as_ref(),
+                );
+                self.reference = Some(backend.element_handle(element));
+            }
+        }
 
-                if let Some(next_sibling) = next_sibling {
-                    let next_sibling = &next_sibling;
-                    #[cfg(feature = "web_sys")]
-                    let next_sibling = Some(next_sibling);
-                    parent
-                        .insert_before(&element, next_sibling)
-                        .expect("can't insert tag before next sibling");
-                } else if let Some(next_sibling) = previous_sibling.and_then(|p| p.next_sibling()) {
-                    let next_sibling = &next_sibling;
-                    #[cfg(feature = "web_sys")]
-                    let next_sibling = Some(next_sibling);
-                    parent
-                        .insert_before(&element, next_sibling)
-                        .expect("can't insert tag before next sibling");
-                } else {
-                    #[cfg_attr(
-                        feature = "std_web",
-                        allow(clippy::let_unit_value, unused_variables)
-                    )]
-                    {
-                        let result = parent.append_child(&element);
-                        #[cfg(feature = "web_sys")]
-                        result.expect("can't append node to parent");
-                    }
+        if let Some(ancestor) = ancestor.as_mut().filter(|ancestor| ancestor.hydrated) {
+            if self.selected_values().is_some() {
+                // The selection of a controlled `select` is rendered as `selected` options
+                for child in ancestor.children.children.iter_mut() {
+                    mark_selected(child, &[]);
                 }
-                self.reference = Some(element);
+            }
+            if self.attributes != ancestor.attributes
+                || self.classes != ancestor.classes
+                || self.styles != ancestor.styles
+            {
+                warn!("Hydration mismatch: patching attributes of <{}>", self.tag);
             }
         }
 
-        self.apply_diffs(&ancestor);
+        let handle = self.reference.clone().expect("element expected");
+        let element = backend.handle_element(&handle);
+        self.apply_diffs(backend, element, &ancestor);
 
-        // Every render it removes all listeners and attach it back later
-        // TODO(#943): Compare references of handler to do listeners update better
-        if let Some(ancestor) = ancestor.as_mut() {
-            ancestor.captured.clear();
+        // Keep the attached listeners for the same kinds of events with the new callbacks
+        let mut attached: Vec<_> = match ancestor.as_mut() {
+            Some(ancestor) => ancestor
+                .listeners
+                .drain(..)
+                .zip(ancestor.captured.drain(..))
+                .collect(),
+            None => Vec::new(),
+        };
+        for listener in mem::take(&mut self.listeners) {
+            let position = attached
+                .iter()
+                .position(|(attached_listener, _)| attached_listener.take_callback(&*listener));
+            match (position, &handle) {
+                (Some(position), _) => {
+                    let (attached_listener, captured) = attached.swap_remove(position);
+                    self.listeners.push(attached_listener);
+                    self.captured.push(captured);
+                }
+                (None, Handle::Browser(element)) => {
+                    self.captured.push(activate_listener(&listener, element));
+                    self.listeners.push(listener);
+                }
+                // There are no events outside of the browser
+                (None, Handle::Memory(_)) => self.listeners.push(listener),
+            }
         }
+        // Detaches listeners which are not used anymore
+        drop(attached);
+
+        // Process children
+        self.children
+            .apply(backend, element, None, ancestor.map(|a| a.children.into()));
+
+        self.apply_selection(backend, element);
 
-        let element = self.reference.clone().expect("element expected");
+        let node = backend.element_node(element);
+        self.node_ref.set(Some(backend.node_handle(node.clone())));
+        Some(node)
+    }
+}
 
-        for listener in self.listeners.drain(..) {
-            let handle = listener.attach(&element);
-            self.captured.push(handle);
+impl VRender for VTag {
+    fn render_html(&self, html: &mut String) {
+        let mut attributes: Vec<(&str, &str)> = self
+            .attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        // Sort attributes to produce the same markup on every render
+        attributes.sort();
+
+        let class_str = self.classes.to_string();
+        if !class_str.is_empty() {
+            attributes.insert(0, ("class", &class_str));
+        }
+        let style_str = self.styles.to_string();
+        if !style_str.is_empty() {
+            attributes.push(("style", &style_str));
         }
 
-        // Process children
-        self.children
-            .apply(&element, None, ancestor.map(|a| a.children.into()));
+        let is_textarea = self.tag == "textarea";
+        if let Some(kind) = &self.kind {
+            attributes.push(("type", kind));
+        }
+        let is_select = self.tag == "select";
+        if let Some(value) = self.value.as_ref().filter(|_| !is_textarea && !is_select) {
+            attributes.push(("value", value));
+        }
+        if self.checked {
+            attributes.push(("checked", ""));
+        }
 
-        let node = self.reference.as_ref().map(|e| {
-            let node = cfg_match! {
-                feature = "std_web" => e.as_node(),
-                feature = "web_sys" => e.deref(),
-            };
-            node.to_owned()
-        });
-        self.node_ref.set(node.clone());
-        node
+        html.push('<');
+        html.push_str(&self.tag);
+        for (key, value) in attributes {
+            html.push(' ');
+            html.push_str(key);
+            html.push_str("=\"");
+            escape_attribute(value, html);
+            html.push('"');
+        }
+        html.push('>');
+
+        if VOID_ELEMENTS.contains(&&*self.tag) {
+            return;
+        }
+
+        match (
+            self.value.as_ref().filter(|_| is_textarea),
+            self.selected_values(),
+        ) {
+            (Some(value), _) => escape_html(value, html),
+            (None, Some(selected)) => {
+                // The selection of a `select` is rendered as `selected` options
+                let mut children = VNode::VList(self.children.clone());
+                mark_selected(&mut children, &selected);
+                children.render_html(html);
+            }
+            (None, None) => self.children.render_html(html),
+        }
+
+        html.push_str("</");
+        html.push_str(&self.tag);
+        html.push('>');
+    }
+}
+
+/// Sets the `selected` attribute of the options in `node` which have one of the values.
+fn mark_selected(node: &mut VNode, selected: &[&str]) {
+    match node {
+        VNode::VTag(vtag) if vtag.tag == "option" => {
+            if selected.contains(&vtag.option_value().as_str()) {
+                vtag.add_attribute("selected", &"selected");
+            } else {
+                vtag.attributes.remove("selected");
+            }
+        }
+        VNode::VTag(vtag) if vtag.tag == "optgroup" => {
+            for child in vtag.children.children.iter_mut() {
+                mark_selected(child, selected);
+            }
+        }
+        VNode::VList(vlist) => {
+            for child in vlist.children.iter_mut() {
+                mark_selected(child, selected);
+            }
+        }
+        _ => {}
     }
 }
 
@@ -514,18 +885,13 @@ impl fmt::Debug for VTag {
     }
 }
 
-/// Set `checked` value for the `InputElement`.
-fn set_checked(input: &InputElement, value: bool) {
-    cfg_match! {
-        feature = "std_web" => js!( @(no_return) @{input}.checked = @{value}; ),
-        feature = "web_sys" => input.set_checked(value),
-    };
-}
-
 impl PartialEq for VTag {
     fn eq(&self, other: &VTag) -> bool {
         self.tag == other.tag
+            && self.key == other.key
             && self.value == other.value
+            && self.values == other.values
+            && self.controlled == other.controlled
             && self.kind == other.kind
             && self.checked == other.checked
             && self.listeners.len() == other.listeners.len()
@@ -535,7 +901,9 @@ impl PartialEq for VTag {
                 .map(|l| l.kind())
                 .eq(other.listeners.iter().map(|l| l.kind()))
             && self.attributes == other.attributes
+            && self.properties == other.properties
             && self.classes.eq(&other.classes)
+            && self.styles == other.styles
             && self.children == other.children
     }
 }
@@ -558,9 +926,12 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "web_sys")]
+    use crate::utils::document;
+    use crate::virtual_dom::{Browser, MemoryDom, MemoryNode};
     use crate::{html, Component, ComponentLink, Html, ShouldRender};
     #[cfg(feature = "std_web")]
-    use stdweb::web::{document, IElement};
+    use stdweb::web::{document, IElement, IParentNode};
     #[cfg(feature = "wasm_test")]
     use wasm_bindgen_test::{wasm_bindgen_test as test, wasm_bindgen_test_configure};
 
@@ -785,6 +1156,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn splits_classes_separated_by_whitespace() {
+        let active = "active";
+        let a = html! { <div class=("btn btn-lg", active)></div> };
+        let b = html! { <div class=vec!["btn  btn-lg", active]></div> };
+
+        for node in vec![a, b] {
+            if let VNode::VTag(vtag) = node {
+                assert_eq!(
+                    vtag.classes.iter().collect::<Vec<_>>(),
+                    vec!["btn", "btn-lg", "active"]
+                );
+            } else {
+                panic!("vtag expected");
+            }
+        }
+    }
+
     #[test]
     fn filter_empty_string_classes_vec() {
         let mut classes = vec![""];
@@ -821,7 +1210,12 @@ mod tests {
 
     fn assert_namespace(vtag: &VTag, namespace: &'static str) {
         assert_eq!(
-            vtag.reference.as_ref().unwrap().namespace_uri().unwrap(),
+            vtag.reference
+                .as_ref()
+                .and_then(Handle::browser)
+                .unwrap()
+                .namespace_uri()
+                .unwrap(),
             namespace
         );
     }
@@ -844,17 +1238,17 @@ mod tests {
         let mut svg_node = html! { <svg>{path_node}</svg> };
 
         let svg_tag = assert_vtag(&mut svg_node);
-        svg_tag.apply(&div_el, None, None);
+        svg_tag.apply(&Browser, &div_el, None, None);
         assert_namespace(svg_tag, SVG_NAMESPACE);
         let path_tag = assert_vtag(svg_tag.children.get_mut(0).unwrap());
         assert_namespace(path_tag, SVG_NAMESPACE);
 
         let g_tag = assert_vtag(&mut g_node);
-        g_tag.apply(&div_el, None, None);
+        g_tag.apply(&Browser, &div_el, None, None);
         assert_namespace(g_tag, HTML_NAMESPACE);
         g_tag.reference = None;
 
-        g_tag.apply(&svg_el, None, None);
+        g_tag.apply(&Browser, &svg_el, None, None);
         assert_namespace(g_tag, SVG_NAMESPACE);
     }
 
@@ -924,6 +1318,64 @@ mod tests {
         assert_ne!(a, c);
     }
 
+    #[test]
+    fn it_compares_styles() {
+        let a = html! {
+            <div style="color: red; margin: 0"></div>
+        };
+
+        let b = html! {
+            <div style=vec![("color", "red"), ("margin", "0")]></div>
+        };
+
+        let c = html! {
+            <div style=("color", "blue")></div>
+        };
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn parses_style_declarations() {
+        let a = html! {
+            <div style="color: red;;  background: url('data:image/png;base64,x'); margin:0 ;"></div>
+        };
+
+        if let VNode::VTag(vtag) = a {
+            assert_eq!(vtag.styles.get("color"), Some("red"));
+            assert_eq!(vtag.styles.get("margin"), Some("0"));
+            assert_eq!(
+                vtag.styles.get("background"),
+                Some("url('data:image/png;base64,x')")
+            );
+            assert_eq!(
+                vtag.styles.to_string(),
+                "color: red; background: url('data:image/png;base64,x'); margin: 0;"
+            );
+        } else {
+            panic!("vtag expected");
+        }
+    }
+
+    #[test]
+    fn it_compares_properties() {
+        let a = html! {
+            <input type="checkbox" ~indeterminate=true />
+        };
+
+        let b = html! {
+            <input type="checkbox" ~indeterminate=true />
+        };
+
+        let c = html! {
+            <input type="checkbox" ~indeterminate=false />
+        };
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
     #[test]
     fn it_allows_aria_attributes() {
         let a = html! {
@@ -984,7 +1436,7 @@ mod tests {
         document().body().unwrap().append_child(&parent).unwrap();
 
         let mut elem = html! { <div class=("class-1", "class-2", "class-3")></div> };
-        elem.apply(&parent, None, None);
+        elem.apply(&Browser, &parent, None, None);
 
         let vtag = if let VNode::VTag(vtag) = elem {
             vtag
@@ -997,6 +1449,7 @@ mod tests {
         assert_eq!(
             vtag.reference
                 .as_ref()
+                .and_then(Handle::browser)
                 .unwrap()
                 .get_attribute("class")
                 .unwrap(),
@@ -1010,17 +1463,18 @@ mod tests {
         } else {
             panic!("should be vtag")
         };
-        vtag.apply(&parent, None, Some(VNode::VTag(ancestor)));
+        vtag.apply(&Browser, &parent, None, Some(VNode::VTag(ancestor)));
 
-        let expected = "class-3 class-2 class-1";
-        assert_eq!(vtag.classes.to_string(), expected);
+        // Reordering classes doesn't touch the element
+        assert_eq!(vtag.classes.to_string(), "class-3 class-2 class-1");
         assert_eq!(
             vtag.reference
                 .as_ref()
+                .and_then(Handle::browser)
                 .unwrap()
                 .get_attribute("class")
                 .unwrap(),
-            expected
+            "class-1 class-2 class-3"
         );
     }
 
@@ -1034,7 +1488,7 @@ mod tests {
         document().body().unwrap().append_child(&parent).unwrap();
 
         let mut elem = html! { <div class=("class-1", "class-3")></div> };
-        elem.apply(&parent, None, None);
+        elem.apply(&Browser, &parent, None, None);
 
         let vtag = if let VNode::VTag(vtag) = elem {
             vtag
@@ -1047,6 +1501,7 @@ mod tests {
         assert_eq!(
             vtag.reference
                 .as_ref()
+                .and_then(Handle::browser)
                 .unwrap()
                 .get_attribute("class")
                 .unwrap(),
@@ -1060,17 +1515,333 @@ mod tests {
         } else {
             panic!("should be vtag")
         };
-        vtag.apply(&parent, None, Some(VNode::VTag(ancestor)));
+        vtag.apply(&Browser, &parent, None, Some(VNode::VTag(ancestor)));
 
-        let expected = "class-1 class-2 class-3";
-        assert_eq!(vtag.classes.to_string(), expected);
+        // New classes are appended to the class list of the element
+        assert_eq!(vtag.classes.to_string(), "class-1 class-2 class-3");
         assert_eq!(
             vtag.reference
                 .as_ref()
+                .and_then(Handle::browser)
                 .unwrap()
                 .get_attribute("class")
                 .unwrap(),
-            expected
+            "class-1 class-3 class-2"
+        );
+    }
+
+    #[test]
+    fn preserves_foreign_classes() {
+        let parent = document().create_element("div").unwrap();
+
+        let mut ancestor = html! { <div class=("class-1", "class-2")></div> };
+        ancestor.apply(&Browser, &parent, None, None);
+        let element = parent.first_child().unwrap();
+        #[cfg(feature = "std_web")]
+        let element = Element::try_from(element).unwrap();
+        #[cfg(feature = "web_sys")]
+        let element = element.dyn_into::<Element>().unwrap();
+        element
+            .set_attribute("class", "class-1 class-2 animated")
+            .unwrap();
+
+        let mut vnode = html! { <div class="class-1"></div> };
+        vnode.apply(&Browser, &parent, None, Some(ancestor));
+
+        assert_eq!(element.get_attribute("class").unwrap(), "class-1 animated");
+    }
+
+    #[test]
+    fn patches_memory_dom() {
+        let dom = MemoryDom;
+        let element: MemoryNode = dom.create_element("input", None);
+
+        let mut ancestor = html! {
+            <input class=("a", "b") style="color: red; margin: 0" title="old" value="1" />
+        };
+        let ancestor = assert_vtag(&mut ancestor);
+        ancestor.apply_diffs(&dom, &element, &None);
+        assert_eq!(element.attribute("class").unwrap(), "a b");
+        assert_eq!(
+            element.attribute("style").unwrap(),
+            "color: red; margin: 0;"
         );
+        assert_eq!(element.value(), "1");
+
+        let mut vnode = html! {
+            <input class=("b", "c") style="color: blue" value="2" checked=true />
+        };
+        assert_vtag(&mut vnode).apply_diffs(&dom, &element, &Some(Box::new(ancestor.clone())));
+        assert_eq!(element.attribute("class").unwrap(), "b c");
+        assert_eq!(element.attribute("style").unwrap(), "color: blue;");
+        assert_eq!(element.attribute("title"), None);
+        assert_eq!(element.value(), "2");
+        assert!(element.checked());
+    }
+
+    #[test]
+    fn controls_selected_options() {
+        let dom = MemoryDom;
+        let select: MemoryNode = dom.create_element("select", None);
+        let group = dom.create_element("optgroup", None);
+        let options: Vec<MemoryNode> = (0..3).map(|_| dom.create_element("option", None)).collect();
+        dom.set_attribute(&options[0], "value", "a");
+        dom.insert_before(&options[1], &dom.create_text("b"), None);
+        dom.set_attribute(&options[2], "value", "c");
+        dom.insert_before(&select, &options[0], None);
+        dom.insert_before(&select, &group, None);
+        dom.insert_before(&group, &options[1], None);
+        dom.insert_before(&group, &options[2], None);
+
+        let selected = || options.iter().map(MemoryNode::checked).collect::<Vec<_>>();
+
+        let mut vnode = html! { <select value="b" /> };
+        assert_vtag(&mut vnode).apply_selection(&dom, &select);
+        assert_eq!(selected(), vec![false, true, false]);
+
+        let mut vnode = html! { <select multiple=true values=vec!["a", "c"] /> };
+        assert_vtag(&mut vnode).apply_selection(&dom, &select);
+        assert_eq!(selected(), vec![true, false, true]);
+    }
+
+    #[test]
+    fn browser_controls_selected_options() {
+        let parent = document().create_element("div").unwrap();
+        let selected = || {
+            let options = ["option[value=a]", "optgroup > option", "option[value=c]"];
+            options
+                .iter()
+                .map(|selector| {
+                    let option = parent.query_selector(selector).unwrap().unwrap();
+                    cfg_match! {
+                        feature = "std_web" => js!( return @{option}.selected; ).try_into().unwrap(),
+                        feature = "web_sys" => option.unchecked_into::<web_sys::HtmlOptionElement>().selected(),
+                    }
+                })
+                .collect::<Vec<bool>>()
+        };
+
+        let mut ancestor = html! {
+            <select value="b">
+                <option value="a">{ "A" }</option>
+                <optgroup>
+                    <option>{ "b" }</option>
+                    <option value="c">{ "C" }</option>
+                </optgroup>
+            </select>
+        };
+        ancestor.apply(&Browser, &parent, None, None);
+        assert_eq!(selected(), vec![false, true, false]);
+
+        let mut vnode = html! {
+            <select multiple=true values=vec!["a", "c"]>
+                <option value="a">{ "A" }</option>
+                <optgroup>
+                    <option>{ "b" }</option>
+                    <option value="c">{ "C" }</option>
+                </optgroup>
+            </select>
+        };
+        vnode.apply(&Browser, &parent, None, Some(ancestor));
+        assert_eq!(selected(), vec![true, false, true]);
+    }
+
+    fn check_controlled_value<B: DomBackend>(backend: &B, element: &B::Element) {
+        let value = || backend.value(element).unwrap_or_default();
+
+        let mut ancestor = html! { <input controlled=true value="42" /> };
+        let ancestor = assert_vtag(&mut ancestor);
+        ancestor.apply_diffs(backend, element, &None);
+        assert_eq!(value(), "42");
+
+        // The listeners of the element restore it after handling an event
+        backend.set_value(element, "42a");
+        restore_controlled(backend, element);
+        assert_eq!(value(), "42");
+
+        // The user typed something the component didn't accept
+        backend.set_value(element, "42a");
+        let mut vnode = html! { <input controlled=true value="42" /> };
+        let vnode = assert_vtag(&mut vnode);
+        vnode.apply_diffs(backend, element, &Some(Box::new(ancestor.clone())));
+        assert_eq!(value(), "42");
+
+        // Without control the value of the user is kept
+        backend.set_value(element, "42a");
+        let ancestor = vnode;
+        let mut vnode = html! { <input value="42" /> };
+        assert_vtag(&mut vnode).apply_diffs(backend, element, &Some(Box::new(ancestor.clone())));
+        assert_eq!(value(), "42a");
+        assert_eq!(backend.controlled_value(element), None);
+        restore_controlled(backend, element);
+        assert_eq!(value(), "42a");
+    }
+
+    #[test]
+    fn restores_controlled_value() {
+        let dom = MemoryDom;
+        check_controlled_value(&dom, &dom.create_element("input", None));
+    }
+
+    #[test]
+    fn browser_restores_controlled_value() {
+        check_controlled_value(&Browser, &document().create_element("input").unwrap());
+    }
+
+    #[test]
+    fn reverts_rejected_input() {
+        use crate::Callback;
+
+        let parent = document().create_element("div").unwrap();
+        let mut vnode =
+            html! { <input controlled=true value="42" oninput=Callback::from(|_| ()) /> };
+        vnode.apply(&Browser, &parent, None, None);
+        let vtag = assert_vtag(&mut vnode);
+        let element = vtag.reference.as_ref().unwrap().browser().unwrap().clone();
+
+        // The callback doesn't render the input of the user
+        Browser.set_value(&element, "42a");
+        cfg_match! {
+            feature = "std_web" => js! { @(no_return) @{&element}.dispatchEvent(new Event("input")); },
+            feature = "web_sys" => element
+                .dispatch_event(&web_sys::Event::new("input").unwrap())
+                .unwrap(),
+        };
+        assert_eq!(Browser.value(&element).as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn renders_selected_options() {
+        let vnode = html! {
+            <select value="b">
+                <option value="a" selected=true>{ "A" }</option>
+                <option>{ "b" }</option>
+            </select>
+        };
+        let mut markup = String::new();
+        vnode.render_html(&mut markup);
+        assert_eq!(
+            markup,
+            "<select><option value=\"a\">A</option><option selected=\"selected\">b</option></select>"
+        );
+    }
+
+    #[test]
+    fn takes_callback_of_same_kind() {
+        use crate::html::{onclick, ondoubleclick};
+        use crate::Callback;
+
+        let listener = onclick::Wrapper::new(Callback::from(|_| ()));
+
+        assert!(listener.take_callback(&onclick::Wrapper::new(Callback::from(|_| ()))));
+        assert!(!listener.take_callback(&ondoubleclick::Wrapper::new(Callback::from(|_| ()))));
+    }
+
+    #[test]
+    fn takes_callback_with_same_options() {
+        use crate::html::onscroll;
+        use crate::virtual_dom::ListenerOptions;
+        use crate::Callback;
+
+        let callback: Callback<onscroll::Event> = Callback::from(|_| ());
+        let passive = ListenerOptions {
+            passive: true,
+            ..ListenerOptions::default()
+        };
+        let listener = onscroll::Wrapper::with_options(callback.clone(), passive);
+
+        assert_eq!(listener.options(), Some(passive));
+        assert!(listener.take_callback(&onscroll::Wrapper::with_options(callback.clone(), passive)));
+        assert!(!listener.take_callback(&onscroll::Wrapper::new(callback)));
+    }
+
+    #[test]
+    fn takes_callback_of_custom_listener_with_same_kind() {
+        use crate::html::{CustomListener, RawEvent};
+        use crate::Callback;
+
+        let listener = CustomListener::new("paste", Callback::from(|_: RawEvent| ()));
+
+        assert_eq!(listener.kind(), "paste");
+        assert!(listener.take_callback(&CustomListener::new(
+            "paste",
+            Callback::from(|_: RawEvent| ())
+        )));
+        assert!(!listener.take_callback(&CustomListener::new(
+            "copy",
+            Callback::from(|_: RawEvent| ())
+        )));
+    }
+
+    #[test]
+    fn keeps_attached_listener_with_new_callback() {
+        use crate::Callback;
+        use std::cell::RefCell;
+
+        let parent = document().create_element("div").unwrap();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let button = |name: &'static str| {
+            let log = log.clone();
+            let onclick = Callback::from(move |_| log.borrow_mut().push(name));
+            html! { <button onclick=onclick /> }
+        };
+
+        let mut ancestor = button("first");
+        ancestor.apply(&Browser, &parent, None, None);
+        let attached = assert_vtag(&mut ancestor).listeners[0].clone();
+
+        // A new callback is created on every render, like with `ComponentLink::callback`
+        let mut vnode = button("second");
+        vnode.apply(&Browser, &parent, None, Some(ancestor));
+        let vtag = assert_vtag(&mut vnode);
+        assert!(Rc::ptr_eq(&vtag.listeners[0], &attached));
+        assert_eq!(vtag.captured.len(), 1);
+
+        let element = vtag.reference.as_ref().unwrap().browser().unwrap().clone();
+        cfg_match! {
+            feature = "std_web" => js! { @(no_return) @{element}.click(); },
+            feature = "web_sys" => element
+                .dispatch_event(&web_sys::Event::new("click").unwrap())
+                .unwrap(),
+        };
+        assert_eq!(*log.borrow(), vec!["second"]);
+    }
+
+    #[test]
+    fn adopts_checked_and_selected_state() {
+        let view = || {
+            html! {
+                <div>
+                    <input type="checkbox" checked=true />
+                    <select value="b">
+                        <option value="a">{ "A" }</option>
+                        <option value="b">{ "B" }</option>
+                    </select>
+                </div>
+            }
+        };
+        let mut markup = String::new();
+        view().render_html(&mut markup);
+        let parent = document().create_element("div").unwrap();
+        cfg_match! {
+            feature = "std_web" => js! { @(no_return) @{&parent}.innerHTML = @{markup}; },
+            feature = "web_sys" => parent.set_inner_html(&markup),
+        };
+        let element = parent.query_selector("div").unwrap().unwrap();
+
+        let mut ancestor = VNode::from(VTag::adopt(element));
+        match &assert_vtag(&mut ancestor).children.children[0] {
+            VNode::VTag(input) => {
+                assert!(input.checked);
+                assert_eq!(input.attributes.get("checked"), None);
+            }
+            node => panic!("expected an input but found {:?}", node),
+        }
+
+        // The selected option of the controlled select isn't patched
+        let mut vnode = view();
+        vnode.apply(&Browser, &parent, None, Some(ancestor));
+        let option = parent.query_selector("option[value=b]").unwrap().unwrap();
+        assert!(option.has_attribute("selected"));
     }
 }
@@ -1,7 +1,6 @@
 //! This module contains the implementation of a virtual text node `VText`.
 
-use super::{Reform, VDiff, VNode};
-use crate::utils::document;
+use super::{escape_html, DomBackend, Handle, Reform, VDiff, VNode, VRender};
 use cfg_if::cfg_if;
 use cfg_match::cfg_match;
 use log::warn;
@@ -9,10 +8,12 @@ use std::cmp::PartialEq;
 use std::fmt;
 cfg_if! {
     if #[cfg(feature = "std_web")] {
-        use stdweb::web::{Element, INode, Node, TextNode};
+        use stdweb::unstable::TryFrom;
+        use stdweb::web::{INode, TextNode};
+        #[allow(unused_imports)]
+        use stdweb::{_js_impl, js};
     } else if #[cfg(feature = "web_sys")] {
-        use std::ops::Deref;
-        use web_sys::{Element, Node, Text as TextNode};
+        use web_sys::Text as TextNode;
     }
 }
 
@@ -24,7 +25,9 @@ pub struct VText {
     /// Contains a text of the node.
     pub text: String,
     /// A reference to the `TextNode`.
-    pub reference: Option<TextNode>,
+    pub reference: Option<Handle<TextNode>>,
+    /// Set if the node was adopted from server-rendered markup.
+    hydrated: bool,
 }
 
 impl VText {
@@ -33,31 +36,71 @@ impl VText {
         VText {
             text,
             reference: None,
+            hydrated: false,
         }
     }
+
+    /// Creates a `VText` which represents an existing `TextNode`.
+    pub(crate) fn adopt(text_node: TextNode) -> Self {
+        VText {
+            text: text_node.text_content().unwrap_or_default(),
+            reference: Some(Handle::Browser(text_node)),
+            hydrated: true,
+        }
+    }
+
+    /// Returns true if the node was adopted from server-rendered markup.
+    pub(crate) fn is_hydrated(&self) -> bool {
+        self.hydrated
+    }
+
+    /// Splits an adopted text node after `text`, since adjacent texts end up in a single text
+    /// node of the markup. Returns the rest of the text as a new adopted node, or `None` if
+    /// the node doesn't start with `text`.
+    pub(crate) fn split_adopted(&mut self, text: &str) -> Option<VText> {
+        if !self.hydrated
+            || text.is_empty()
+            || self.text.len() <= text.len()
+            || !self.text.starts_with(text)
+        {
+            return None;
+        }
+        let text_node = self.reference.as_ref()?.browser()?;
+        // Offsets in the DOM count UTF-16 code units
+        let offset = text.encode_utf16().count() as u32;
+        let rest = cfg_match! {
+            feature = "std_web" => TextNode::try_from(js!(return @{text_node}.splitText(@{offset});)),
+            feature = "web_sys" => text_node.split_text(offset),
+        };
+        let rest = rest.ok()?;
+        self.text.truncate(text.len());
+        Some(VText::adopt(rest))
+    }
 }
 
 impl VDiff for VText {
     /// Remove VText from parent.
-    fn detach(&mut self, parent: &Element) -> Option<Node> {
+    fn detach<B: DomBackend>(&mut self, backend: &B, parent: &B::Element) -> Option<B::Node> {
         let node = self
             .reference
             .take()
             .expect("tried to remove not rendered VText from DOM");
-        let next_sibling = node.next_sibling();
-        if parent.remove_child(&node).is_err() {
+        let node = backend.text_node(backend.handle_text(&node));
+        let next_sibling = backend.next_sibling(&node);
+        if !backend.remove_child(parent, &node) {
             warn!("Node not found to remove VText");
         }
         next_sibling
     }
 
     /// Renders virtual node over existing `TextNode`, but only if value of text had changed.
-    fn apply(
+    fn apply<B: DomBackend>(
         &mut self,
-        parent: &Element,
-        previous_sibling: Option<&Node>,
+        backend: &B,
+        parent: &B::Element,
+        previous_sibling: Option<&B::Node>,
         ancestor: Option<VNode>,
-    ) -> Option<Node> {
+    ) -> Option<B::Node> {
         assert!(
             self.reference.is_none(),
             "reference is ignored so must not be set"
@@ -68,55 +111,53 @@ impl VDiff for VText {
                 Some(VNode::VText(mut vtext)) => {
                     self.reference = vtext.reference.take();
                     if self.text != vtext.text {
-                        if let Some(ref element) = self.reference {
-                            element.set_node_value(Some(&self.text));
+                        if vtext.hydrated {
+                            warn!(
+                                "Hydration mismatch: expected text {:?} but found {:?}",
+                                self.text, vtext.text
+                            );
+                        }
+                        if let Some(ref text_node) = self.reference {
+                            backend.set_text(backend.handle_text(text_node), &self.text);
                         }
                     }
                     Reform::Keep
                 }
-                Some(mut vnode) => Reform::Before(vnode.detach(parent)),
+                Some(mut vnode) => {
+                    if vnode.is_hydrated() {
+                        warn!(
+                            "Hydration mismatch: expected {:?} but found {:?}",
+                            self, vnode
+                        );
+                    }
+                    Reform::Before(vnode.detach(backend, parent))
+                }
                 None => Reform::Before(None),
             }
         };
         match reform {
             Reform::Keep => {}
             Reform::Before(next_sibling) => {
-                let element = document().create_text_node(&self.text);
-                if let Some(next_sibling) = next_sibling {
-                    let next_sibling = &next_sibling;
-                    #[cfg(feature = "web_sys")]
-                    let next_sibling = Some(next_sibling);
-                    parent
-                        .insert_before(&element, next_sibling)
-                        .expect("can't insert text before the next sibling");
-                } else if let Some(next_sibling) = previous_sibling.and_then(|p| p.next_sibling()) {
-                    let next_sibling = &next_sibling;
-                    #[cfg(feature = "web_sys")]
-                    let next_sibling = Some(next_sibling);
-                    parent
-                        .insert_before(&element, next_sibling)
-                        .expect("can't insert text before next_sibling");
-                } else {
-                    #[cfg_attr(
-                        feature = "std_web",
-                        allow(clippy::let_unit_value, unused_variables)
-                    )]
-                    {
-                        let result = parent.append_child(&element);
-                        #[cfg(feature = "web_sys")]
-                        result.expect("can't append node to parent");
-                    }
-                }
-                self.reference = Some(element);
+                let text_node = backend.create_text(&self.text);
+                let next_sibling =
+                    next_sibling.or_else(|| previous_sibling.and_then(|p| backend.next_sibling(p)));
+                backend.insert_before(
+                    parent,
+                    &backend.text_node(&text_node),
+                    next_sibling.as_ref(),
+                );
+                self.reference = Some(backend.text_handle(text_node));
             }
         }
-        self.reference.as_ref().map(|t| {
-            let node = cfg_match! {
-                feature = "std_web" => t.as_node(),
-                feature = "web_sys" => t.deref().deref(),
-            };
-            node.to_owned()
-        })
+        self.reference
+            .as_ref()
+            .map(|t| backend.text_node(backend.handle_text(t)))
+    }
+}
+
+impl VRender for VText {
+    fn render_html(&self, html: &mut String) {
+        escape_html(&self.text, html);
     }
 }
 